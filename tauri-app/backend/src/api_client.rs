@@ -0,0 +1,1075 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// 等待控制器启动/停止时的轮询间隔，从初始值开始翻倍直到上限
+const POLL_INITIAL_DELAY: Duration = Duration::from_millis(100);
+const POLL_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// 控制器连接参数，来自当前配置的 external-controller / secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerSettings {
+    pub host: String,
+    pub port: u16,
+    pub secret: Option<String>,
+    pub transport: ControllerTransport,
+}
+
+/// 与控制器通信的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControllerTransport {
+    /// external-controller
+    Http,
+    /// external-controller-tls，`ca_cert` 为校验证书用的 PEM 文件路径或 PEM 内容
+    Https { ca_cert: Option<String> },
+    /// external-controller-unix
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl ControllerSettings {
+    /// 从当前配置文件读取控制器参数，读取失败时回退到平台默认值
+    pub async fn load() -> Self {
+        let config = match crate::config_manager::get_config_manager().await {
+            Ok(manager) => manager.read_config().await.ok(),
+            Err(_) => None,
+        };
+
+        match config {
+            Some(config) => Self::from_config(&config),
+            None => Self::fallback(),
+        }
+    }
+
+    /// 解析配置中的 external-controller(-unix/-tls) 与 secret
+    ///
+    /// 同时声明多种方式时优先使用 Unix socket，其次 TLS，最后是明文 HTTP。
+    pub fn from_config(config: &serde_json::Value) -> Self {
+        let fallback = Self::fallback();
+
+        let secret = non_empty_str(config, "secret").map(|s| s.to_string());
+
+        #[cfg(unix)]
+        if let Some(socket) = non_empty_str(config, "external-controller-unix") {
+            return Self {
+                secret,
+                transport: ControllerTransport::Unix(resolve_config_path(socket)),
+                ..fallback
+            };
+        }
+
+        let (controller, transport) = match non_empty_str(config, "external-controller-tls") {
+            Some(controller) => (
+                Some(controller),
+                ControllerTransport::Https {
+                    ca_cert: controller_ca_cert(config),
+                },
+            ),
+            None => (
+                non_empty_str(config, "external-controller"),
+                ControllerTransport::Http,
+            ),
+        };
+
+        let controller = match controller {
+            Some(controller) => controller,
+            None => {
+                return Self {
+                    secret,
+                    transport,
+                    ..fallback
+                }
+            }
+        };
+
+        let (host, port) = match controller.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().unwrap_or(fallback.port)),
+            None => (controller, fallback.port),
+        };
+
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let host = match host {
+            // 监听所有地址时通过回环地址访问
+            "" | "0.0.0.0" => fallback.host,
+            "::" => "::1".to_string(),
+            other => other.to_string(),
+        };
+
+        Self {
+            host,
+            port,
+            secret,
+            transport,
+        }
+    }
+
+    fn fallback() -> Self {
+        let (host, port) = crate::platform_config::PlatformConfig::common()
+            .map(|common| (common.api_host, common.api_port))
+            .unwrap_or_else(|_| ("127.0.0.1".to_string(), 9090));
+
+        Self {
+            host,
+            port,
+            secret: None,
+            transport: ControllerTransport::Http,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        let scheme = match self.transport {
+            ControllerTransport::Https { .. } => "https",
+            _ => "http",
+        };
+
+        if self.host.contains(':') {
+            format!("{}://[{}]:{}", scheme, self.host, self.port)
+        } else {
+            format!("{}://{}:{}", scheme, self.host, self.port)
+        }
+    }
+}
+
+fn non_empty_str<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    config
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+}
+
+/// 相对路径按 mihomo 的习惯相对于配置目录解析
+fn resolve_config_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path;
+    }
+
+    match crate::platform_config::PlatformPaths::config_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path,
+    }
+}
+
+/// TLS 控制器的 CA：优先使用平台配置中的 api_ca_cert，否则信任配置中 tls.certificate 指定的证书
+fn controller_ca_cert(config: &serde_json::Value) -> Option<String> {
+    let configured = crate::platform_config::PlatformConfig::common()
+        .ok()
+        .and_then(|common| common.api_ca_cert)
+        .filter(|path| !path.trim().is_empty());
+
+    if let Some(path) = configured {
+        return Some(
+            crate::platform_config::PathResolver::resolve(&path)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or(path),
+        );
+    }
+
+    let certificate = config
+        .get("tls")
+        .and_then(|tls| non_empty_str(tls, "certificate"))?;
+
+    if certificate.starts_with("-----BEGIN") {
+        Some(certificate.to_string())
+    } else {
+        Some(
+            resolve_config_path(certificate)
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
+
+/// 读取 PEM 证书，`source` 可以是文件路径或 PEM 内容
+fn load_pem(source: &str) -> Result<Vec<u8>> {
+    if source.trim_start().starts_with("-----BEGIN") {
+        return Ok(source.as_bytes().to_vec());
+    }
+
+    std::fs::read(source).with_context(|| format!("Failed to read CA certificate: {}", source))
+}
+
+/// mihomo RESTful API 客户端
+#[derive(Debug, Clone)]
+pub struct MihomoApiClient {
+    transport: Transport,
+    base_url: String,
+    secret: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Transport {
+    Http(reqwest::Client),
+    #[cfg(unix)]
+    Unix {
+        client: hyper::Client<hyperlocal::UnixConnector>,
+        socket: PathBuf,
+    },
+}
+
+impl MihomoApiClient {
+    pub fn new(settings: &ControllerSettings) -> Result<Self> {
+        let builder = reqwest::Client::builder().no_proxy();
+
+        let transport = match &settings.transport {
+            ControllerTransport::Http => {
+                Transport::Http(builder.build().context("Failed to create HTTP client")?)
+            }
+            ControllerTransport::Https { ca_cert } => {
+                let builder = match ca_cert {
+                    Some(ca_cert) => {
+                        let certificate = reqwest::Certificate::from_pem(&load_pem(ca_cert)?)
+                            .context("Invalid controller CA certificate")?;
+                        builder.add_root_certificate(certificate)
+                    }
+                    None => builder,
+                };
+                Transport::Http(builder.build().context("Failed to create HTTPS client")?)
+            }
+            #[cfg(unix)]
+            ControllerTransport::Unix(socket) => {
+                use hyperlocal::UnixClientExt;
+                Transport::Unix {
+                    client: hyper::Client::unix(),
+                    socket: socket.clone(),
+                }
+            }
+        };
+
+        Ok(Self {
+            transport,
+            base_url: settings.base_url(),
+            secret: settings.secret.clone(),
+        })
+    }
+
+    /// 根据当前配置创建客户端
+    pub async fn from_active_config() -> Result<Self> {
+        Self::new(&ControllerSettings::load().await)
+    }
+
+    /// 构造带鉴权头的请求
+    pub fn request(&self, method: reqwest::Method, path: &str) -> ApiRequest<'_> {
+        ApiRequest {
+            client: self,
+            method,
+            path: path.to_string(),
+            body: None,
+            timeout: None,
+            error: None,
+        }
+    }
+
+    pub async fn version(&self) -> Result<VersionInfo> {
+        let response = self
+            .request(reqwest::Method::GET, "/version")
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .context("Failed to query mihomo version")?;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse version response")
+    }
+
+    /// 检查控制器是否可访问
+    pub async fn is_healthy(&self) -> bool {
+        self.version().await.is_ok()
+    }
+
+    /// 以指数退避轮询 /version，直到控制器响应或超过 `deadline`，返回核心版本
+    pub async fn wait_until_ready(&self, deadline: Duration) -> Result<VersionInfo> {
+        let started = Instant::now();
+        let mut delay = POLL_INITIAL_DELAY;
+
+        loop {
+            match self.version().await {
+                Ok(version) => return Ok(version),
+                Err(e) if started.elapsed() + delay >= deadline => {
+                    return Err(e.context(format!(
+                        "Controller did not respond within {}s",
+                        deadline.as_secs_f32()
+                    )))
+                }
+                Err(_) => {
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(POLL_MAX_DELAY);
+                }
+            }
+        }
+    }
+
+    /// 以指数退避轮询，直到控制器不再响应；超过 `deadline` 仍在响应时返回 false
+    pub async fn wait_until_stopped(&self, deadline: Duration) -> bool {
+        let started = Instant::now();
+        let mut delay = POLL_INITIAL_DELAY;
+
+        while self.is_healthy().await {
+            if started.elapsed() + delay >= deadline {
+                return false;
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(POLL_MAX_DELAY);
+        }
+        true
+    }
+
+    pub async fn proxies(&self) -> Result<ProxiesResponse> {
+        let response = self
+            .request(reqwest::Method::GET, "/proxies")
+            .send()
+            .await
+            .context("Failed to fetch proxies")?;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse proxies response")
+    }
+
+    pub async fn select_proxy(&self, group_name: &str, proxy_name: &str) -> Result<()> {
+        let body = SelectProxyRequest {
+            name: proxy_name.to_string(),
+        };
+
+        let response = self
+            .request(
+                reqwest::Method::PUT,
+                &format!("/proxies/{}", urlencoding::encode(group_name)),
+            )
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to switch proxy")?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    pub async fn proxy_delay(&self, proxy_name: &str, query: &DelayQuery) -> Result<DelayResponse> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/proxies/{}/delay", urlencoding::encode(proxy_name)),
+            )
+            .query(query)
+            .timeout(query.request_timeout())
+            .send()
+            .await
+            .context("Failed to test proxy delay")?;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Invalid delay response")
+    }
+
+    pub async fn group_delay(
+        &self,
+        group_name: &str,
+        query: &DelayQuery,
+    ) -> Result<HashMap<String, u32>> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/group/{}/delay", urlencoding::encode(group_name)),
+            )
+            .query(query)
+            .timeout(query.request_timeout())
+            .send()
+            .await
+            .context("Failed to test group delay")?;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Invalid group delay response")
+    }
+
+    pub async fn connections(&self) -> Result<ConnectionsSnapshot> {
+        let response = self
+            .request(reqwest::Method::GET, "/connections")
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to fetch connections")?;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse connections response")
+    }
+
+    pub async fn close_connection(&self, id: &str) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::DELETE,
+                &format!("/connections/{}", urlencoding::encode(id)),
+            )
+            .send()
+            .await
+            .context("Failed to close connection")?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    pub async fn close_all_connections(&self) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, "/connections")
+            .send()
+            .await
+            .context("Failed to close connections")?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    pub async fn proxy_providers(&self) -> Result<HashMap<String, ProxyProviderInfo>> {
+        let response = self
+            .request(reqwest::Method::GET, "/providers/proxies")
+            .send()
+            .await
+            .context("Failed to fetch proxy providers")?;
+
+        let body: ProvidersResponse<ProxyProviderInfo> = check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse proxy providers response")?;
+        Ok(body.providers)
+    }
+
+    pub async fn rule_providers(&self) -> Result<HashMap<String, RuleProviderInfo>> {
+        let response = self
+            .request(reqwest::Method::GET, "/providers/rules")
+            .send()
+            .await
+            .context("Failed to fetch rule providers")?;
+
+        let body: ProvidersResponse<RuleProviderInfo> = check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse rule providers response")?;
+        Ok(body.providers)
+    }
+
+    /// 让核心重新拉取 provider 内容
+    pub async fn update_provider(&self, kind: ProviderKind, name: &str) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::PUT,
+                &format!("{}/{}", kind.api_path(), urlencoding::encode(name)),
+            )
+            .timeout(Duration::from_secs(60))
+            .send()
+            .await
+            .with_context(|| format!("Failed to update provider {}", name))?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// 对代理 provider 中的节点执行健康检查，核心在检查完成后才返回
+    pub async fn healthcheck_provider(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!(
+                    "/providers/proxies/{}/healthcheck",
+                    urlencoding::encode(name)
+                ),
+            )
+            .timeout(Duration::from_secs(60))
+            .send()
+            .await
+            .with_context(|| format!("Failed to health check provider {}", name))?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// 修改运行中核心的配置（PATCH /configs），不写入配置文件
+    pub async fn patch_configs(&self, patch: &RuntimeConfigPatch) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::PATCH, "/configs")
+            .json(patch)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to patch configs")?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// 打开按行推送的流式接口（如 /traffic、/logs）
+    pub async fn stream_lines(&self, path: &str) -> Result<LineStream> {
+        let response = self
+            .request(reqwest::Method::GET, path)
+            .send()
+            .await
+            .with_context(|| format!("Failed to open stream {}", path))?;
+
+        Ok(LineStream {
+            response: check_status(response).await?,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// 让运行中的核心从指定路径重新加载配置（PUT /configs）
+    pub async fn reload_config(&self, path: &str, force: bool) -> Result<()> {
+        let body = ReloadConfigRequest {
+            path: path.to_string(),
+            payload: String::new(),
+        };
+
+        let response = self
+            .request(reqwest::Method::PUT, "/configs")
+            .query(&[("force", force)])
+            .json(&body)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .context("Failed to reload config")?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// 请求 mihomo 关闭
+    pub async fn shutdown(&self) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, "/configs")
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .context("Failed to send shutdown command")?;
+
+        // secret 错误（401）或不支持（404）时核心并未收到关闭请求
+        check_status(response).await?;
+        Ok(())
+    }
+}
+
+/// 待发送的控制器请求，按传输方式分别发出
+pub struct ApiRequest<'a> {
+    client: &'a MihomoApiClient,
+    method: reqwest::Method,
+    path: String,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    error: Option<anyhow::Error>,
+}
+
+impl ApiRequest<'_> {
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        match serde_urlencoded::to_string(query) {
+            Ok(query) if !query.is_empty() => {
+                self.path
+                    .push(if self.path.contains('?') { '&' } else { '?' });
+                self.path.push_str(&query);
+            }
+            Ok(_) => {}
+            Err(e) => self.error = Some(anyhow::Error::new(e).context("Invalid query")),
+        }
+        self
+    }
+
+    pub fn json<T: Serialize + ?Sized>(mut self, body: &T) -> Self {
+        match serde_json::to_vec(body) {
+            Ok(body) => self.body = Some(body),
+            Err(e) => self.error = Some(anyhow::Error::new(e).context("Invalid request body")),
+        }
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub async fn send(self) -> Result<ApiResponse> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let client = self.client;
+        match &client.transport {
+            Transport::Http(http) => {
+                let mut builder =
+                    http.request(self.method, format!("{}{}", client.base_url, self.path));
+                if let Some(secret) = &client.secret {
+                    builder = builder.bearer_auth(secret);
+                }
+                if let Some(body) = self.body {
+                    builder = builder
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(body);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                Ok(ApiResponse::Http(builder.send().await?))
+            }
+            #[cfg(unix)]
+            Transport::Unix {
+                client: unix,
+                socket,
+            } => {
+                let mut builder = hyper::Request::builder()
+                    .method(self.method)
+                    .uri(hyper::Uri::from(hyperlocal::Uri::new(socket, &self.path)));
+                if let Some(secret) = &client.secret {
+                    builder = builder
+                        .header(reqwest::header::AUTHORIZATION, format!("Bearer {}", secret));
+                }
+                let body = match self.body {
+                    Some(body) => {
+                        builder = builder.header(reqwest::header::CONTENT_TYPE, "application/json");
+                        hyper::Body::from(body)
+                    }
+                    None => hyper::Body::empty(),
+                };
+
+                let request = unix.request(builder.body(body)?);
+                // 超时只作用于等待响应头，流式接口的响应体可以一直读取
+                let response = match self.timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, request)
+                        .await
+                        .map_err(|_| anyhow::anyhow!("Request timed out"))??,
+                    None => request.await?,
+                };
+                Ok(ApiResponse::Unix(response))
+            }
+        }
+    }
+}
+
+/// 控制器响应
+pub enum ApiResponse {
+    Http(reqwest::Response),
+    #[cfg(unix)]
+    Unix(hyper::Response<hyper::Body>),
+}
+
+impl ApiResponse {
+    pub fn status(&self) -> reqwest::StatusCode {
+        match self {
+            ApiResponse::Http(response) => response.status(),
+            #[cfg(unix)]
+            ApiResponse::Unix(response) => response.status(),
+        }
+    }
+
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
+        match self {
+            ApiResponse::Http(response) => Ok(response.json().await?),
+            #[cfg(unix)]
+            ApiResponse::Unix(response) => {
+                let body = hyper::body::to_bytes(response.into_body()).await?;
+                Ok(serde_json::from_slice(&body)?)
+            }
+        }
+    }
+
+    /// 读取下一块响应体追加到 `buffer`，响应结束时返回 false
+    async fn read_chunk(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+        let chunk = match self {
+            ApiResponse::Http(response) => response.chunk().await?,
+            #[cfg(unix)]
+            ApiResponse::Unix(response) => {
+                use hyper::body::HttpBody;
+                response.body_mut().data().await.transpose()?
+            }
+        };
+
+        match chunk {
+            Some(chunk) => {
+                buffer.extend_from_slice(&chunk);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// 流式响应的逐行读取器
+pub struct LineStream {
+    response: ApiResponse,
+    buffer: Vec<u8>,
+}
+
+impl LineStream {
+    /// 读取下一行非空内容，流结束时返回 None
+    pub async fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            if let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                return Ok(Some(line));
+            }
+
+            if !self
+                .response
+                .read_chunk(&mut self.buffer)
+                .await
+                .context("Failed to read stream")?
+            {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// 非 2xx 响应转换为错误，并带上 mihomo 返回的 message
+async fn check_status(response: ApiResponse) -> Result<ApiResponse> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    match response.json::<ApiErrorResponse>().await {
+        Ok(body) => Err(anyhow::anyhow!("{} - {}", status, body.message)),
+        Err(_) => Err(anyhow::anyhow!("{}", status)),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiErrorResponse {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    #[serde(default)]
+    pub meta: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxiesResponse {
+    pub proxies: HashMap<String, ProxyInfo>,
+}
+
+/// /proxies 中的单个代理或代理组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyInfo {
+    pub name: String,
+    pub r#type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub now: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub all: Vec<String>,
+    #[serde(default)]
+    pub history: Vec<ProxyHistory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alive: Option<bool>,
+    /// 其余字段原样保留，供前端使用
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProxyInfo {
+    pub fn is_group(&self) -> bool {
+        matches!(
+            self.r#type.as_str(),
+            "Selector" | "URLTest" | "Fallback" | "LoadBalance" | "Relay"
+        )
+    }
+
+    /// 是否为真实的出站节点（排除代理组、Direct、Reject等）
+    pub fn is_node(&self) -> bool {
+        !self.is_group()
+            && !matches!(
+                self.r#type.as_str(),
+                "Direct" | "Reject" | "RejectDrop" | "Compatible" | "Pass"
+            )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyHistory {
+    pub time: String,
+    pub delay: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectProxyRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvidersResponse<T> {
+    pub providers: HashMap<String, T>,
+}
+
+/// provider 类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Proxy,
+    Rule,
+}
+
+impl ProviderKind {
+    fn api_path(&self) -> &'static str {
+        match self {
+            ProviderKind::Proxy => "/providers/proxies",
+            ProviderKind::Rule => "/providers/rules",
+        }
+    }
+}
+
+/// /providers/proxies 中的单个代理 provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyProviderInfo {
+    pub name: String,
+    pub r#type: String,
+    /// HTTP、File、Inline，代理组内置的 provider 为 Compatible
+    pub vehicle_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription_info: Option<SubscriptionInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_url: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub proxies: Vec<ProxyInfo>,
+}
+
+/// 订阅响应头 subscription-userinfo 中的流量信息，单位为字节，Expire 为 Unix 时间戳
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct SubscriptionInfo {
+    pub upload: u64,
+    pub download: u64,
+    pub total: u64,
+    pub expire: u64,
+}
+
+/// /providers/rules 中的单个规则 provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleProviderInfo {
+    pub name: String,
+    pub r#type: String,
+    pub vehicle_type: String,
+    #[serde(default)]
+    pub behavior: String,
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub rule_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// 代理模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    Rule,
+    Global,
+    Direct,
+}
+
+impl ProxyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyMode::Rule => "rule",
+            ProxyMode::Global => "global",
+            ProxyMode::Direct => "direct",
+        }
+    }
+}
+
+/// PATCH /configs 支持的运行时字段，未设置的字段不会发送
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RuntimeConfigPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<ProxyMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<crate::core_log::LogLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_lan: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadConfigRequest {
+    pub path: String,
+    pub payload: String,
+}
+
+/// 延迟测试参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayQuery {
+    pub url: String,
+    pub timeout: u32,
+    /// 期望的 HTTP 状态码，如 "204" 或 "200-299"，未指定时由核心判断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl DelayQuery {
+    pub fn new(url: &str, timeout: u32) -> Self {
+        Self {
+            url: url.to_string(),
+            timeout,
+            expected: None,
+        }
+    }
+
+    /// HTTP 请求超时在测试超时基础上留出余量
+    fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout as u64) + Duration::from_secs(2)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayResponse {
+    pub delay: u32,
+}
+
+/// /connections 返回的连接快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionsSnapshot {
+    #[serde(default)]
+    pub download_total: u64,
+    #[serde(default)]
+    pub upload_total: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub connections: Vec<Connection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    #[serde(default)]
+    pub upload: u64,
+    #[serde(default)]
+    pub download: u64,
+    #[serde(default)]
+    pub start: String,
+    /// 出站链路，从实际节点到最外层代理组
+    #[serde(default)]
+    pub chains: Vec<String>,
+    #[serde(default)]
+    pub rule: String,
+    #[serde(default)]
+    pub rule_payload: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConnectionMetadata {
+    pub network: String,
+    pub r#type: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub source_port: String,
+    pub destination_port: String,
+    pub host: String,
+    pub sniff_host: String,
+    pub dns_mode: String,
+    pub process: String,
+    pub process_path: String,
+    pub special_proxy: String,
+    pub special_rules: String,
+    pub remote_destination: String,
+}
+
+/// mihomo 在没有连接时返回 `"connections": null`
+fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// /logs 流中的一条日志
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogMessage {
+    pub r#type: String,
+    pub payload: String,
+}
+
+/// /traffic 流中的一条采样，单位为字节/秒
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrafficSample {
+    pub up: u64,
+    pub down: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_controller_from_config() {
+        let config = serde_json::json!({
+            "external-controller": "0.0.0.0:19090",
+            "secret": "s3cret"
+        });
+        let settings = ControllerSettings::from_config(&config);
+        assert_eq!(settings.port, 19090);
+        assert_eq!(settings.secret.as_deref(), Some("s3cret"));
+        assert_eq!(
+            settings.base_url(),
+            format!("http://{}:19090", settings.host)
+        );
+
+        let settings = ControllerSettings::from_config(&serde_json::json!({
+            "external-controller": "[::]:9097"
+        }));
+        assert_eq!(settings.base_url(), "http://[::1]:9097");
+        assert_eq!(settings.secret, None);
+
+        let settings = ControllerSettings::from_config(&serde_json::json!({
+            "external-controller": "127.0.0.1:9090",
+            "external-controller-tls": "127.0.0.1:9443",
+            "tls": { "certificate": "/etc/mihomo/server.crt" }
+        }));
+        assert_eq!(settings.base_url(), "https://127.0.0.1:9443");
+        assert!(matches!(
+            settings.transport,
+            ControllerTransport::Https { .. }
+        ));
+
+        #[cfg(unix)]
+        {
+            let settings = ControllerSettings::from_config(&serde_json::json!({
+                "external-controller": "127.0.0.1:9090",
+                "external-controller-unix": "/run/mihomo/controller.sock"
+            }));
+            assert_eq!(
+                settings.transport,
+                ControllerTransport::Unix(PathBuf::from("/run/mihomo/controller.sock"))
+            );
+        }
+    }
+}
@@ -9,6 +9,8 @@ pub struct RestoreResult {
     pub service_running: bool,
     pub message: String,
     pub requires_restart: bool,
+    /// 恢复后应用到运行中核心的结果
+    pub apply: Option<crate::mihomo::ApplyResult>,
 }
 
 /// 备份配置文件，保留最近5个备份
@@ -49,14 +51,33 @@ pub async fn restore_config(backup_filename: &str) -> Result<RestoreResult> {
             service_running: false,
             message: format!("备份文件不存在: {}", backup_filename),
             requires_restart: false,
+            apply: None,
         });
     }
 
     let config_path = get_config_path()?;
 
+    // 核心拒绝的备份不覆盖当前配置
+    if let Err(e) = crate::mihomo::ensure_core_accepts_file(&backup_path).await {
+        return Ok(RestoreResult {
+            success: false,
+            service_running: false,
+            message: format!("备份配置未通过核心检查: {}", e),
+            requires_restart: false,
+            apply: None,
+        });
+    }
+
     // 检查服务是否在运行
     let service_running = crate::mihomo::is_mihomo_running().await;
 
+    // 记录恢复前的配置，用于判断能否热重载
+    let previous = if config_path.exists() {
+        crate::config::load_config().await.ok()
+    } else {
+        None
+    };
+
     // 在恢复前先备份当前配置
     if config_path.exists() {
         let temp_backup = format!(
@@ -70,19 +91,33 @@ pub async fn restore_config(backup_filename: &str) -> Result<RestoreResult> {
     // 恢复配置文件
     fs::copy(&backup_path, &config_path).context("Failed to restore backup")?;
 
-    let message = if service_running {
-        format!("✓ 配置已从备份恢复: {}。请重启服务以应用更改。", backup_filename)
+    // 服务运行中时直接应用到核心
+    let apply = if service_running {
+        Some(crate::mihomo::apply_config(previous.as_ref()).await)
+    } else {
+        None
+    };
+    let requires_restart = apply.as_ref().is_some_and(|a| !a.success);
+
+    let message = if requires_restart {
+        format!(
+            "✓ 配置已从备份恢复: {}。请重启服务以应用更改。",
+            backup_filename
+        )
+    } else if service_running {
+        format!("✓ 配置已从备份恢复并已应用: {}", backup_filename)
     } else {
         format!("✓ 配置已从备份恢复: {}", backup_filename)
     };
 
     println!("{}", message);
-    
+
     Ok(RestoreResult {
         success: true,
         service_running,
         message,
-        requires_restart: service_running,
+        requires_restart,
+        apply,
     })
 }
 
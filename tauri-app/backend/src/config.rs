@@ -1,3 +1,4 @@
+use crate::config_manager::WriteMethod;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -54,7 +55,7 @@ pub async fn load_config() -> Result<serde_json::Value> {
     Ok(config)
 }
 
-pub async fn save_config(config: serde_json::Value) -> Result<()> {
+pub async fn save_config(config: serde_json::Value) -> Result<WriteMethod> {
     let config_path = get_config_path()?;
 
     if let Some(parent) = config_path.parent() {
@@ -66,7 +67,7 @@ pub async fn save_config(config: serde_json::Value) -> Result<()> {
 }
 
 #[allow(dead_code)]
-pub async fn save_config_no_backup(config: serde_json::Value) -> Result<()> {
+pub async fn save_config_no_backup(config: serde_json::Value) -> Result<WriteMethod> {
     let config_path = get_config_path()?;
 
     if let Some(parent) = config_path.parent() {
@@ -78,7 +79,7 @@ pub async fn save_config_no_backup(config: serde_json::Value) -> Result<()> {
 }
 
 /// 原子更新配置，防止竞态条件
-pub async fn update_config<F>(updater: F) -> Result<()>
+pub async fn update_config<F>(updater: F) -> Result<WriteMethod>
 where
     F: FnOnce(&mut serde_json::Value) -> Result<()>,
 {
@@ -92,7 +93,7 @@ where
     manager.update_config(updater).await
 }
 
-pub async fn set_tun_mode(enable: bool) -> Result<()> {
+pub async fn set_tun_mode(enable: bool) -> Result<WriteMethod> {
     let mut config = load_config().await?;
 
     // Ensure tun section exists
@@ -327,7 +328,8 @@ async fn create_default_config(_config_path: &PathBuf) -> Result<()> {
         ]
     });
 
-    save_config(default_config).await
+    save_config(default_config).await?;
+    Ok(())
 }
 
 pub fn get_config_path() -> Result<PathBuf> {
@@ -335,14 +337,21 @@ pub fn get_config_path() -> Result<PathBuf> {
     Ok(config_dir.join("config.yaml"))
 }
 
-fn get_mihomo_config_dir() -> Result<PathBuf> {
+pub fn get_mihomo_config_dir() -> Result<PathBuf> {
     // 使用统一的平台配置系统
     crate::platform_config::PlatformPaths::config_dir()
 }
 
+/// YAML 合并键
+const MERGE_KEY: &str = "<<";
+
+/// 将 yaml-rust 解析出的节点转换为 JSON
+///
+/// 锚点和别名在解析时已由 `YamlLoader` 展开；这里处理合并键 `<<`，
+/// 并把整数、布尔等非字符串键转成字符串键
 pub fn yaml_to_json(yaml: &Yaml) -> Result<serde_json::Value> {
     match yaml {
-        Yaml::Real(f) => Ok(serde_json::json!(f.parse::<f64>().unwrap_or(0.0))),
+        Yaml::Real(f) => Ok(real_to_json(f)),
         Yaml::Integer(i) => Ok(serde_json::json!(*i)),
         Yaml::String(s) => Ok(serde_json::json!(s)),
         Yaml::Boolean(b) => Ok(serde_json::json!(*b)),
@@ -355,15 +364,162 @@ pub fn yaml_to_json(yaml: &Yaml) -> Result<serde_json::Value> {
         }
         Yaml::Hash(hash) => {
             let mut json_obj = serde_json::Map::new();
+            let mut merges = Vec::new();
             for (key, value) in hash {
-                if let Yaml::String(key_str) = key {
-                    json_obj.insert(key_str.clone(), yaml_to_json(value)?);
+                if matches!(key, Yaml::String(k) if k == MERGE_KEY) {
+                    merges.push(value);
+                    continue;
+                }
+                json_obj.insert(yaml_key_to_string(key)?, yaml_to_json(value)?);
+            }
+
+            // 显式写出的键优先；合并列表中靠前的映射优先
+            for merge in merges {
+                let sources = match merge {
+                    Yaml::Array(items) => items.iter().collect(),
+                    other => vec![other],
+                };
+                for source in sources {
+                    match yaml_to_json(source)? {
+                        serde_json::Value::Object(merged) => {
+                            for (key, value) in merged {
+                                json_obj.entry(key).or_insert(value);
+                            }
+                        }
+                        _ => anyhow::bail!("YAML merge key value must be a mapping"),
+                    }
                 }
             }
             Ok(serde_json::Value::Object(json_obj))
         }
-        Yaml::Alias(_) => Err(anyhow::anyhow!("YAML aliases not supported")),
+        Yaml::Alias(_) => Err(anyhow::anyhow!("Unresolved YAML alias")),
         Yaml::Null => Ok(serde_json::Value::Null),
         Yaml::BadValue => Err(anyhow::anyhow!("Bad YAML value")),
     }
 }
+
+/// JSON 无法表示 `.inf`/`.nan`，保留原始文本
+fn real_to_json(text: &str) -> serde_json::Value {
+    match Yaml::Real(text.to_string()).as_f64() {
+        Some(f) if f.is_finite() => serde_json::json!(f),
+        _ => serde_json::json!(text),
+    }
+}
+
+fn yaml_key_to_string(key: &Yaml) -> Result<String> {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => Ok(s.clone()),
+        Yaml::Integer(i) => Ok(i.to_string()),
+        Yaml::Boolean(b) => Ok(b.to_string()),
+        Yaml::Null => Ok("null".to_string()),
+        _ => Err(anyhow::anyhow!("Unsupported YAML mapping key: {:?}", key)),
+    }
+}
+
+/// YAML 中表示无穷和非数的写法，yaml_to_json 将其保留为字符串
+const NON_FINITE_FLOATS: &[&str] = &[
+    ".inf", ".Inf", ".INF", "+.inf", "+.Inf", "+.INF", "-.inf", "-.Inf", "-.INF", ".nan", ".NaN",
+    ".NAN",
+];
+
+/// yaml_to_json 的逆转换，用于整体重写 YAML
+///
+/// 把 `.inf`/`.nan` 文本还原为浮点数，把整数、布尔形式的键还原为非字符串键；
+/// 原本就带引号的 ".inf" 或 "7890" 也会被还原，JSON 中已无法区分。
+pub fn json_to_yaml(value: &serde_json::Value) -> serde_yaml::Value {
+    use serde_yaml::Value as YamlValue;
+
+    match value {
+        serde_json::Value::Null => YamlValue::Null,
+        serde_json::Value::Bool(b) => YamlValue::Bool(*b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => YamlValue::Number(i.into()),
+            (None, Some(u)) => YamlValue::Number(u.into()),
+            _ => YamlValue::Number(n.as_f64().unwrap_or_default().into()),
+        },
+        serde_json::Value::String(s) => match Yaml::Real(s.clone()).as_f64() {
+            Some(f) if NON_FINITE_FLOATS.contains(&s.as_str()) => YamlValue::Number(f.into()),
+            _ => YamlValue::String(s.clone()),
+        },
+        serde_json::Value::Array(items) => {
+            YamlValue::Sequence(items.iter().map(json_to_yaml).collect())
+        }
+        serde_json::Value::Object(map) => YamlValue::Mapping(
+            map.iter()
+                .map(|(key, value)| (json_key_to_yaml(key), json_to_yaml(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// 还原被 yaml_to_json 转成字符串的整数和布尔键
+pub fn json_key_to_yaml(key: &str) -> serde_yaml::Value {
+    match key {
+        "true" => serde_yaml::Value::Bool(true),
+        "false" => serde_yaml::Value::Bool(false),
+        // 只还原规范写法，"007" 之类保持字符串
+        _ => match key.parse::<i64>() {
+            Ok(i) if i.to_string() == key => serde_yaml::Value::Number(i.into()),
+            _ => serde_yaml::Value::String(key.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_yaml_to_json_resolves_anchors_and_merges() {
+        let text = r#"
+base: &base
+  type: select
+  interval: 300
+  url: http://a
+extra: &extra
+  url: http://b
+  lazy: true
+proxy-groups:
+  - <<: *base
+    name: PROXY
+    url: http://override
+  - <<: [*extra, *base]
+    name: AUTO
+ports:
+  7890: http
+  true: yes
+limits:
+  upper: .inf
+  lower: -.Inf
+  ratio: 0.5
+"#;
+        let docs = YamlLoader::load_from_str(text).unwrap();
+        let json = yaml_to_json(&docs[0]).unwrap();
+
+        assert_eq!(
+            json["proxy-groups"][0],
+            serde_json::json!({
+                "name": "PROXY",
+                "type": "select",
+                "interval": 300,
+                "url": "http://override",
+            })
+        );
+        assert_eq!(
+            json["proxy-groups"][1],
+            serde_json::json!({
+                "name": "AUTO",
+                "type": "select",
+                "interval": 300,
+                "url": "http://b",
+                "lazy": true,
+            })
+        );
+        assert_eq!(json["ports"]["7890"], "http");
+        assert_eq!(json["ports"]["true"], "yes");
+        assert_eq!(json["limits"]["upper"], ".inf");
+        assert_eq!(json["limits"]["lower"], "-.Inf");
+        assert_eq!(json["limits"]["ratio"], 0.5);
+    }
+}
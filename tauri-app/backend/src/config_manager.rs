@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use fs2::FileExt;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -7,6 +8,18 @@ use tokio::sync::RwLock;
 use tracing::{info, warn};
 use yaml_rust::YamlLoader;
 
+/// 配置文件的写入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMethod {
+    /// 在原文件上局部修改，保留注释和键顺序
+    Edited,
+    /// 无法局部修改，整个文件重新生成，注释和键顺序丢失
+    Rewritten,
+    /// 文件原本不存在
+    Created,
+}
+
 pub struct ConfigManager {
     config_path: PathBuf,
     lock: Arc<RwLock<()>>,
@@ -54,22 +67,22 @@ impl ConfigManager {
         Ok(json_value)
     }
 
-    pub async fn write_config(&self, config: serde_json::Value) -> Result<()> {
+    pub async fn write_config(&self, config: serde_json::Value) -> Result<WriteMethod> {
         self.write_config_with_options(config, true).await
     }
 
-    pub async fn write_config_with_options(&self, config: serde_json::Value, create_backup: bool) -> Result<()> {
+    pub async fn write_config_with_options(
+        &self,
+        config: serde_json::Value,
+        create_backup: bool,
+    ) -> Result<WriteMethod> {
         let _guard = self.lock.write().await;
 
         if create_backup {
             self.create_backup().await?;
         }
 
-        let yaml_value: serde_yaml::Value =
-            serde_json::from_value(config).context("Failed to convert from JSON")?;
-
-        let yaml_content =
-            serde_yaml::to_string(&yaml_value).context("Failed to serialize YAML")?;
+        let (yaml_content, method) = self.render_config(config)?;
 
         let temp_path = self.config_path.with_extension("yaml.tmp");
 
@@ -97,11 +110,11 @@ impl ConfigManager {
 
         info!("Config saved successfully: {:?}", self.config_path);
 
-        Ok(())
+        Ok(method)
     }
 
     /// 原子更新配置 - 防止竞态条件
-    pub async fn update_config<F>(&self, updater: F) -> Result<()>
+    pub async fn update_config<F>(&self, updater: F) -> Result<WriteMethod>
     where
         F: FnOnce(&mut serde_json::Value) -> Result<()>,
     {
@@ -114,9 +127,7 @@ impl ConfigManager {
         updater(&mut config)?;
         
         // 写回配置
-        self.write_config_internal(config).await?;
-        
-        Ok(())
+        self.write_config_internal(config).await
     }
 
     // 内部方法，假设已经持有锁
@@ -143,14 +154,10 @@ impl ConfigManager {
         Ok(json_value)
     }
 
-    async fn write_config_internal(&self, config: serde_json::Value) -> Result<()> {
+    async fn write_config_internal(&self, config: serde_json::Value) -> Result<WriteMethod> {
         self.create_backup().await?;
 
-        let yaml_value: serde_yaml::Value =
-            serde_json::from_value(config).context("Failed to convert from JSON")?;
-
-        let yaml_content =
-            serde_yaml::to_string(&yaml_value).context("Failed to serialize YAML")?;
+        let (yaml_content, method) = self.render_config(config)?;
 
         let temp_path = self.config_path.with_extension("yaml.tmp");
         std::fs::write(&temp_path, yaml_content).context("Failed to write temp file")?;
@@ -169,8 +176,28 @@ impl ConfigManager {
 
         std::fs::rename(&temp_path, &self.config_path).context("Failed to rename temp file")?;
         info!("Config saved successfully: {:?}", self.config_path);
-        
-        Ok(())
+
+        Ok(method)
+    }
+
+    /// 在现有文件上做局部修改，保留注释、键顺序和引号；无法局部修改时完整重写
+    ///
+    /// 返回的 [`WriteMethod`] 告诉调用方注释是否已丢失。
+    fn render_config(&self, config: serde_json::Value) -> Result<(String, WriteMethod)> {
+        let method = match std::fs::read_to_string(&self.config_path) {
+            Ok(original) => match crate::yaml_edit::update(&original, &config) {
+                Ok(updated) => return Ok((updated, WriteMethod::Edited)),
+                Err(e) => {
+                    warn!("Rewriting whole config, targeted edit failed: {:#}", e);
+                    WriteMethod::Rewritten
+                }
+            },
+            Err(_) => WriteMethod::Created,
+        };
+
+        let yaml_value = crate::config::json_to_yaml(&config);
+        let content = serde_yaml::to_string(&yaml_value).context("Failed to serialize YAML")?;
+        Ok((content, method))
     }
 
     async fn create_backup(&self) -> Result<()> {
@@ -257,3 +284,49 @@ pub async fn get_config_manager() -> Result<ConfigManager> {
         None => Err(anyhow::anyhow!("ConfigManager not initialized")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_keeps_non_finite_floats_and_non_string_keys() {
+        let original = "\
+base: &base
+  interval: 300
+group:
+  <<: *base
+  name: auto
+limits:
+  upper: .inf
+  lower: -.inf
+ports:
+  7890: http
+  true: enabled
+";
+        let path = std::env::temp_dir().join(format!("render-{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, original).unwrap();
+        let manager = ConfigManager::new(path.clone());
+
+        // 修改通过合并键继承的键，只能整体重写
+        let docs = YamlLoader::load_from_str(original).unwrap();
+        let mut config = crate::config::yaml_to_json(&docs[0]).unwrap();
+        config["group"]["interval"] = serde_json::json!(60);
+
+        let (rendered, method) = manager.render_config(config.clone()).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(method, WriteMethod::Rewritten);
+
+        let docs = YamlLoader::load_from_str(&rendered).unwrap();
+        let yaml = &docs[0];
+        assert_eq!(yaml["limits"]["upper"].as_f64(), Some(f64::INFINITY));
+        assert_eq!(yaml["limits"]["lower"].as_f64(), Some(f64::NEG_INFINITY));
+        assert_eq!(yaml["ports"][7890].as_str(), Some("http"));
+        let ports = yaml["ports"].as_hash().unwrap();
+        assert_eq!(
+            ports.get(&yaml_rust::Yaml::Boolean(true)),
+            Some(&yaml_rust::Yaml::String("enabled".to_string()))
+        );
+        assert_eq!(crate::config::yaml_to_json(yaml).unwrap(), config);
+    }
+}
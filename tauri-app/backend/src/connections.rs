@@ -0,0 +1,65 @@
+use crate::api_client::MihomoApiClient;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use tokio::time::{interval, Duration};
+use tracing::{debug, info};
+
+/// 快照推送的最小间隔，避免频繁请求 /connections
+const MIN_INTERVAL_MS: u64 = 500;
+
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+
+lazy_static! {
+    static ref STREAM_TASK: Mutex<Option<tauri::async_runtime::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// 开始定时推送连接快照（connections-update 事件），已在推送时按新间隔重启
+pub fn start_stream(app: tauri::AppHandle, interval_ms: Option<u64>) -> u64 {
+    let interval_ms = interval_ms
+        .unwrap_or(DEFAULT_INTERVAL_MS)
+        .max(MIN_INTERVAL_MS);
+
+    let task = tauri::async_runtime::spawn(async move {
+        let mut ticker = interval(Duration::from_millis(interval_ms));
+
+        loop {
+            ticker.tick().await;
+
+            let snapshot = match MihomoApiClient::from_active_config().await {
+                Ok(client) => client.connections().await,
+                Err(e) => Err(e),
+            };
+
+            match snapshot {
+                Ok(snapshot) => crate::events::emit_connections_update(
+                    &app,
+                    crate::events::ConnectionsEvent {
+                        snapshot,
+                        timestamp: crate::events::get_current_timestamp(),
+                    },
+                ),
+                // 核心未运行时跳过本次推送
+                Err(e) => debug!("Connections snapshot unavailable: {}", e),
+            }
+        }
+    });
+
+    if let Some(previous) = STREAM_TASK.lock().unwrap().replace(task) {
+        previous.abort();
+    }
+
+    info!("Connections stream started (interval: {}ms)", interval_ms);
+    interval_ms
+}
+
+/// 停止连接快照推送，返回之前是否在推送
+pub fn stop_stream() -> bool {
+    match STREAM_TASK.lock().unwrap().take() {
+        Some(task) => {
+            task.abort();
+            info!("Connections stream stopped");
+            true
+        }
+        None => false,
+    }
+}
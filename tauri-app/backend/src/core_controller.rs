@@ -0,0 +1,378 @@
+use crate::data_path::{DataPathState, DataPathStatus};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{watch, Mutex};
+use tracing::{info, warn};
+
+/// 核心服务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreState {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    /// 核心意外停止（API 无响应或重启失败）
+    Crashed,
+    Restarting,
+}
+
+impl CoreState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoreState::Stopped => "stopped",
+            CoreState::Starting => "starting",
+            CoreState::Running => "running",
+            CoreState::Stopping => "stopping",
+            CoreState::Crashed => "crashed",
+            CoreState::Restarting => "restarting",
+        }
+    }
+}
+
+/// 核心当前状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreStatus {
+    pub state: CoreState,
+    /// 由本应用启动时的 PID，系统服务或外部启动的核心为 None
+    pub process_id: Option<u32>,
+    /// 最近一次失败的原因
+    pub error: Option<String>,
+    /// 进入当前状态的时间
+    pub since: u64,
+    /// 经由代理端口的数据通路探测结果
+    pub data_path: DataPathStatus,
+}
+
+/// 唯一的核心状态来源，命令、托盘、watchdog 和系统服务命令都通过它启停核心
+pub struct CoreController {
+    app_handle: tauri::AppHandle,
+    status_tx: watch::Sender<CoreStatus>,
+    running_tx: watch::Sender<bool>,
+    // 串行化状态转换
+    transition: Mutex<()>,
+    /// 核心是被停止命令停下的（而不是应用刚启动、尚未启动过核心）
+    manual_stop: AtomicBool,
+}
+
+impl CoreController {
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
+        Self {
+            app_handle,
+            status_tx: watch::channel(CoreStatus {
+                state: CoreState::Stopped,
+                process_id: None,
+                error: None,
+                since: crate::events::get_current_timestamp(),
+                data_path: DataPathStatus::new(DataPathState::Disabled),
+            })
+            .0,
+            running_tx: watch::channel(false).0,
+            transition: Mutex::new(()),
+            manual_stop: AtomicBool::new(false),
+        }
+    }
+
+    pub fn status(&self) -> CoreStatus {
+        self.status_tx.borrow().clone()
+    }
+
+    pub fn state(&self) -> CoreState {
+        self.status_tx.borrow().state
+    }
+
+    /// 核心是否由停止命令手动停止；再次启动后清除
+    pub fn manually_stopped(&self) -> bool {
+        self.manual_stop.load(Ordering::Relaxed)
+    }
+
+    pub fn clear_manual_stop(&self) {
+        self.manual_stop.store(false, Ordering::Relaxed);
+    }
+
+    /// 订阅核心是否可用（Running）的变化，供流量、日志等后台任务使用
+    pub fn subscribe_running(&self) -> watch::Receiver<bool> {
+        self.running_tx.subscribe()
+    }
+
+    /// 启动由本应用管理的核心
+    pub async fn start(&self) -> anyhow::Result<u32> {
+        self.transition(
+            CoreState::Starting,
+            crate::mihomo::start_mihomo(),
+            |pid| (CoreState::Running, Some(*pid)),
+            Some(CoreState::Stopped),
+        )
+        .await
+    }
+
+    /// 停止核心；失败时保持之前的状态
+    pub async fn stop(&self) -> anyhow::Result<()> {
+        self.transition(
+            CoreState::Stopping,
+            crate::mihomo::stop_mihomo(),
+            |_| (CoreState::Stopped, None),
+            None,
+        )
+        .await
+    }
+
+    /// 重启核心，失败时进入 Crashed
+    pub async fn restart(&self) -> anyhow::Result<u32> {
+        self.transition(
+            CoreState::Restarting,
+            crate::mihomo::start_mihomo(),
+            |pid| (CoreState::Running, Some(*pid)),
+            Some(CoreState::Crashed),
+        )
+        .await
+    }
+
+    /// 结束所有 mihomo 进程，包括不是由本应用启动的实例
+    pub async fn kill_all(&self) -> anyhow::Result<()> {
+        self.transition(
+            CoreState::Stopping,
+            crate::process_manager::kill_all_instances(),
+            |_| (CoreState::Stopped, None),
+            None,
+        )
+        .await
+    }
+
+    /// 通过系统服务（systemd / WinSW）启动，等待控制器就绪后进入 Running
+    pub async fn start_service<E: Display>(
+        &self,
+        action: impl Future<Output = Result<String, E>>,
+    ) -> Result<String, String> {
+        self.transition(
+            CoreState::Starting,
+            service_then_ready(action),
+            |_| (CoreState::Running, None),
+            Some(CoreState::Stopped),
+        )
+        .await
+    }
+
+    pub async fn stop_service<E: Display>(
+        &self,
+        action: impl Future<Output = Result<String, E>>,
+    ) -> Result<String, String> {
+        self.transition(
+            CoreState::Stopping,
+            async { action.await.map_err(|e| e.to_string()) },
+            |_| (CoreState::Stopped, None),
+            None,
+        )
+        .await
+    }
+
+    pub async fn restart_service<E: Display>(
+        &self,
+        action: impl Future<Output = Result<String, E>>,
+    ) -> Result<String, String> {
+        self.transition(
+            CoreState::Restarting,
+            service_then_ready(action),
+            |_| (CoreState::Running, None),
+            Some(CoreState::Crashed),
+        )
+        .await
+    }
+
+    /// 执行可能重启核心的操作（应用配置、切换核心等），按结果更新状态
+    pub async fn with_restart<T>(
+        &self,
+        action: impl Future<Output = T>,
+        restarted_pid: impl FnOnce(&T) -> Option<u32>,
+    ) -> T {
+        let _guard = self.transition.lock().await;
+        let previous = self.status();
+
+        let result = action.await;
+
+        if let Some(pid) = restarted_pid(&result) {
+            self.set_status(CoreState::Running, Some(pid), None);
+            crate::crash_history::capture_config().await;
+        } else if previous.state == CoreState::Running {
+            if crate::mihomo::is_mihomo_running().await {
+                // 配置可能已热重载
+                crate::crash_history::capture_config().await;
+            } else {
+                self.crashed("Mihomo stopped while applying changes", previous.since)
+                    .await;
+            }
+        }
+
+        result
+    }
+
+    /// 由 watchdog 定期调用：根据 API 健康状态修正 Running / Crashed
+    ///
+    /// 有状态转换进行中时不做判断，避免把启动或停止过程误判为崩溃。
+    pub async fn check_health(&self) -> CoreState {
+        let Ok(_guard) = self.transition.try_lock() else {
+            return self.state();
+        };
+
+        let healthy = crate::mihomo::is_mihomo_running().await;
+        let status = self.status();
+
+        match (status.state, healthy) {
+            (CoreState::Running, false) => {
+                warn!("Mihomo API is not responding, service may have stopped");
+                self.crashed("Mihomo API stopped responding", status.since)
+                    .await;
+            }
+            // 系统服务或外部启动的核心
+            (CoreState::Stopped | CoreState::Crashed, true) => {
+                info!("Detected running mihomo that was started outside the app");
+                self.set_status(CoreState::Running, status.process_id, None);
+                crate::crash_history::capture_config().await;
+            }
+            _ => {}
+        }
+
+        self.state()
+    }
+
+    /// 进入 Crashed 并记录崩溃现场
+    async fn crashed(&self, reason: &str, running_since: u64) {
+        self.set_status(CoreState::Crashed, None, Some(reason.to_string()));
+        crate::crash_history::record(reason, running_since).await;
+    }
+
+    /// 由 watchdog 更新数据通路探测结果；核心未运行时只接受 Disabled，状态变化时推送事件
+    pub fn set_data_path(&self, data_path: DataPathStatus) {
+        let mut state_changed = false;
+        self.status_tx.send_if_modified(|status| {
+            if status.state != CoreState::Running && data_path.state != DataPathState::Disabled {
+                return false;
+            }
+            state_changed = status.data_path.state != data_path.state;
+            let modified = status.data_path != data_path;
+            status.data_path = data_path;
+            modified
+        });
+
+        if state_changed {
+            let status = self.status();
+            info!("Mihomo data path: {:?}", status.data_path.state);
+            self.emit(status);
+        }
+    }
+
+    async fn transition<T, E: Display>(
+        &self,
+        via: CoreState,
+        action: impl Future<Output = Result<T, E>>,
+        on_success: impl FnOnce(&T) -> (CoreState, Option<u32>),
+        on_failure: Option<CoreState>,
+    ) -> Result<T, E> {
+        let _guard = self.transition.lock().await;
+        let previous = self.status();
+
+        self.set_status(via, previous.process_id, None);
+        let result = action.await;
+
+        match &result {
+            Ok(value) => {
+                let (state, process_id) = on_success(value);
+                self.set_status(state, process_id, None);
+                self.manual_stop
+                    .store(state == CoreState::Stopped, Ordering::Relaxed);
+                if state == CoreState::Running {
+                    crate::crash_history::capture_config().await;
+                }
+            }
+            Err(e) => {
+                let error = e.to_string();
+                match on_failure {
+                    // 重启失败（新核心在就绪前退出）也记录崩溃现场
+                    Some(CoreState::Crashed) => self.crashed(&error, previous.since).await,
+                    Some(state) => self.set_status(state, None, Some(error)),
+                    None => self.set_status(previous.state, previous.process_id, Some(error)),
+                }
+            }
+        }
+
+        result
+    }
+
+    fn set_status(&self, state: CoreState, process_id: Option<u32>, error: Option<String>) {
+        let previous = self.status();
+        if previous.state != state {
+            info!(
+                "Mihomo state: {} -> {}",
+                previous.state.as_str(),
+                state.as_str()
+            );
+        }
+
+        let running = state == CoreState::Running;
+        self.running_tx.send_if_modified(|current| {
+            let changed = *current != running;
+            *current = running;
+            changed
+        });
+
+        // 核心离开 Running 后之前的探测结果不再有效
+        let data_path = if previous.data_path.state == DataPathState::Disabled
+            || (previous.state == CoreState::Running && running)
+        {
+            previous.data_path
+        } else {
+            DataPathStatus::new(DataPathState::Unknown)
+        };
+
+        let status = CoreStatus {
+            state,
+            process_id,
+            error,
+            since: if previous.state == state {
+                previous.since
+            } else {
+                crate::events::get_current_timestamp()
+            },
+            data_path,
+        };
+        self.status_tx.send_replace(status.clone());
+        self.emit(status);
+    }
+
+    fn emit(&self, status: CoreStatus) {
+        crate::events::emit_mihomo_status(
+            &self.app_handle,
+            crate::events::MihomoStatusEvent {
+                running: status.state == CoreState::Running,
+                process_id: status.process_id,
+                state: status.state,
+                error: status.error,
+                data_path: status.data_path,
+                timestamp: crate::events::get_current_timestamp(),
+            },
+        );
+    }
+}
+
+/// 执行系统服务命令后等待控制器就绪
+async fn service_then_ready<E: Display>(
+    action: impl Future<Output = Result<String, E>>,
+) -> Result<String, String> {
+    let message = action.await.map_err(|e| e.to_string())?;
+    crate::mihomo::wait_until_ready()
+        .await
+        .map_err(|e| format!("{}, but mihomo did not become ready: {}", message, e))?;
+    Ok(message)
+}
+
+/// 等待核心状态变为 `running`，发送端关闭时返回 false
+pub async fn wait_for_core(status: &mut watch::Receiver<bool>, running: bool) -> bool {
+    while *status.borrow_and_update() != running {
+        if status.changed().await.is_err() {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,366 @@
+use crate::platform_config::{PathResolver, PlatformConfig, PlatformPaths};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+use tokio::time::Duration;
+use tracing::warn;
+
+const CORE_SETTINGS_FILE_NAME: &str = "core-settings.json";
+
+/// 旧版核心的文件名，找不到 mihomo 时作为后备
+const LEGACY_BINARY_STEM: &str = "clash-meta";
+
+/// `mihomo -v` 的最长等待时间
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 核心可执行文件的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoreSource {
+    /// 用户指定的路径
+    UserOverride,
+    /// 平台配置中的 system_paths
+    SystemPath,
+    /// `$PATH` 环境变量
+    EnvPath,
+    /// 随应用打包的核心
+    Bundled,
+}
+
+/// 当前使用的核心信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreInfo {
+    pub path: PathBuf,
+    pub source: CoreSource,
+    pub version: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub go_version: Option<String>,
+    pub build_time: Option<String>,
+    pub build_tags: Vec<String>,
+    /// `mihomo -v` 的原始输出
+    pub raw: String,
+}
+
+/// 持久化的核心设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CoreSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    core_path: Option<String>,
+    /// 上一次切换前的路径，用于回滚（None 表示自动查找）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous_core_path: Option<String>,
+    /// 是否切换过核心；为 false 时 previous_core_path 没有意义
+    #[serde(default)]
+    has_previous: bool,
+}
+
+/// 按 用户路径 -> system_paths -> $PATH -> 打包资源 的顺序查找核心
+///
+/// 所有位置都找不到 mihomo 时，再按同样顺序查找旧版的 clash-meta。
+pub struct CoreLocator {
+    binary_name: String,
+    legacy_binary_name: String,
+    user_override: Option<PathBuf>,
+    system_paths: Vec<PathBuf>,
+}
+
+impl CoreLocator {
+    /// 使用当前平台配置和已保存的用户路径创建
+    pub fn from_platform() -> Result<Self> {
+        let platform = PlatformConfig::current_platform()?;
+        let system_paths = PlatformPaths::system_paths()?;
+        let legacy_binary_name = if platform.mihomo_binary.ends_with(".exe") {
+            format!("{}.exe", LEGACY_BINARY_STEM)
+        } else {
+            LEGACY_BINARY_STEM.to_string()
+        };
+
+        Ok(Self {
+            binary_name: platform.mihomo_binary,
+            legacy_binary_name,
+            user_override: read_user_override()?,
+            system_paths,
+        })
+    }
+
+    /// 返回第一个存在的核心及其来源
+    pub fn locate(&self) -> Result<(PathBuf, CoreSource)> {
+        // 用户指定的路径不存在时直接报错，不悄悄回退到其他核心
+        if let Some(path) = &self.user_override {
+            if !path.is_file() {
+                anyhow::bail!("Configured mihomo core not found: {}", path.display());
+            }
+            return Ok((path.clone(), CoreSource::UserOverride));
+        }
+
+        if let Some(found) = self.locate_named(&self.binary_name, self.system_paths.clone())? {
+            return Ok(found);
+        }
+
+        // 系统路径中与 mihomo 同目录的 clash-meta
+        let legacy_system_paths = self
+            .system_paths
+            .iter()
+            .map(|path| path.with_file_name(&self.legacy_binary_name))
+            .collect();
+        if let Some((path, source)) =
+            self.locate_named(&self.legacy_binary_name, legacy_system_paths)?
+        {
+            warn!(
+                "{} not found, falling back to legacy core {}",
+                self.binary_name,
+                path.display()
+            );
+            return Ok((path, source));
+        }
+
+        Err(anyhow::anyhow!(
+            "未找到 {} 或 {} 文件。请确保 mihomo 已安装在系统路径或应用目录中",
+            self.binary_name,
+            self.legacy_binary_name
+        ))
+    }
+
+    fn locate_named(
+        &self,
+        binary_name: &str,
+        system_paths: Vec<PathBuf>,
+    ) -> Result<Option<(PathBuf, CoreSource)>> {
+        if let Some(path) = system_paths.into_iter().find(|path| path.is_file()) {
+            return Ok(Some((path, CoreSource::SystemPath)));
+        }
+
+        if let Some(path) = Self::search_env_path(binary_name) {
+            return Ok(Some((path, CoreSource::EnvPath)));
+        }
+
+        Ok(Self::bundled_paths(binary_name)?
+            .into_iter()
+            .find(|path| path.is_file())
+            .map(|path| (path, CoreSource::Bundled)))
+    }
+
+    fn search_env_path(binary_name: &str) -> Option<PathBuf> {
+        let paths = std::env::var_os("PATH")?;
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(binary_name))
+            .find(|path| path.is_file())
+    }
+
+    fn bundled_paths(binary_name: &str) -> Result<Vec<PathBuf>> {
+        let app_dir = std::env::current_exe()
+            .context("获取应用目录失败")?
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("无法获取应用目录"))?
+            .to_path_buf();
+
+        Ok(vec![
+            app_dir.join(binary_name),                   // 应用目录根目录
+            app_dir.join("resources").join(binary_name), // resources 子目录（Tauri 打包位置）
+        ])
+    }
+}
+
+/// 查找当前使用的核心可执行文件
+pub async fn locate() -> Result<PathBuf> {
+    Ok(locate_with_source().await?.0)
+}
+
+/// 查找核心并运行 `mihomo -v` 读取版本信息
+pub async fn detect() -> Result<CoreInfo> {
+    let (path, source) = locate_with_source().await?;
+    let raw = read_version(&path).await?;
+    Ok(parse_version_output(path, source, &raw))
+}
+
+/// 设置用户指定的核心路径，`None` 恢复自动查找；新路径需能正常输出版本
+pub async fn set_user_override(path: Option<String>) -> Result<CoreInfo> {
+    if let Some(path) = &path {
+        let resolved = PathResolver::resolve(path)?;
+        read_version(&resolved)
+            .await
+            .with_context(|| format!("{} is not a usable mihomo core", resolved.display()))?;
+    }
+
+    switch_user_override(path).await?;
+    detect().await
+}
+
+/// 切换用户指定的核心路径，并记住之前的路径以便回滚
+pub async fn switch_user_override(path: Option<String>) -> Result<()> {
+    blocking(move || {
+        let mut settings = load_settings()?;
+        settings.previous_core_path = std::mem::replace(&mut settings.core_path, path);
+        settings.has_previous = true;
+        save_settings(&settings)
+    })
+    .await
+}
+
+/// 恢复上一次切换前的核心路径；当前路径成为新的回滚目标，再次回滚即可撤销
+pub async fn rollback_user_override() -> Result<()> {
+    blocking(|| {
+        let mut settings = load_settings()?;
+        // 旧版本的设置文件没有 has_previous，有上一次路径即可回滚
+        if !settings.has_previous && settings.previous_core_path.is_none() {
+            anyhow::bail!("No previous core to roll back to");
+        }
+        std::mem::swap(&mut settings.core_path, &mut settings.previous_core_path);
+        settings.has_previous = true;
+        save_settings(&settings)
+    })
+    .await
+}
+
+/// 当前用户指定的核心路径（已解析环境变量）
+pub async fn user_override() -> Result<Option<PathBuf>> {
+    blocking(read_user_override).await
+}
+
+fn read_user_override() -> Result<Option<PathBuf>> {
+    load_settings()?
+        .core_path
+        .map(|path| PathResolver::resolve(&path))
+        .transpose()
+}
+
+async fn locate_with_source() -> Result<(PathBuf, CoreSource)> {
+    blocking(|| CoreLocator::from_platform()?.locate()).await
+}
+
+/// 设置文件读写和路径检查在阻塞线程池中进行
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .context("Core settings task failed")?
+}
+
+/// 运行 `mihomo -v`，返回其输出
+pub async fn read_version(path: &Path) -> Result<String> {
+    let mut command = TokioCommand::new(path);
+    command.arg("-v").stdin(Stdio::null()).kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(VERSION_TIMEOUT, command.output())
+        .await
+        .map_err(|_| anyhow::anyhow!("mihomo -v timed out"))?
+        .with_context(|| format!("Failed to run {}", path.display()))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} -v exited with {}: {}",
+            path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 解析形如以下的输出：
+///
+/// ```text
+/// Mihomo Meta v1.18.1 linux amd64 with go1.22.0 Mon Feb  5 12:34:56 UTC 2024
+/// Use tags: with_gvisor
+/// ```
+fn parse_version_output(path: PathBuf, source: CoreSource, raw: &str) -> CoreInfo {
+    let mut info = CoreInfo {
+        path,
+        source,
+        version: None,
+        os: None,
+        arch: None,
+        go_version: None,
+        build_time: None,
+        build_tags: Vec::new(),
+        raw: raw.to_string(),
+    };
+
+    for line in raw.lines().map(str::trim) {
+        if let Some(tags) = line.strip_prefix("Use tags:") {
+            info.build_tags = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            continue;
+        }
+
+        let (head, build) = match line.split_once(" with ") {
+            Some((head, build)) => (head, Some(build)),
+            None => (line, None),
+        };
+
+        let mut tokens = head.split_whitespace();
+        let Some(version) = tokens.find(|t| t.starts_with('v') || t.starts_with("alpha")) else {
+            continue;
+        };
+        info.version = Some(version.to_string());
+        info.os = tokens.next().map(str::to_string);
+        info.arch = tokens.next().map(str::to_string);
+
+        if let Some((go, time)) = build.map(|b| b.split_once(' ').unwrap_or((b, ""))) {
+            info.go_version = Some(go.to_string());
+            info.build_time = Some(time.trim().to_string()).filter(|t| !t.is_empty());
+        }
+    }
+
+    info
+}
+
+fn settings_path() -> Result<PathBuf> {
+    Ok(PlatformPaths::config_dir()?.join(CORE_SETTINGS_FILE_NAME))
+}
+
+fn load_settings() -> Result<CoreSettings> {
+    let path = settings_path()?;
+    if !path.exists() {
+        return Ok(CoreSettings::default());
+    }
+
+    let content = std::fs::read_to_string(&path).context("Failed to read core settings")?;
+    serde_json::from_str(&content).context("Failed to parse core settings")
+}
+
+/// 先写临时文件再重命名，切换核心时不会留下写了一半的设置
+fn save_settings(settings: &CoreSettings) -> Result<()> {
+    let content =
+        serde_json::to_string_pretty(settings).context("Failed to serialize core settings")?;
+
+    let path = settings_path()?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, content).context("Failed to write core settings")?;
+    std::fs::rename(&tmp_path, &path).context("Failed to replace core settings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_output() {
+        let raw = "Mihomo Meta v1.18.1 linux amd64 with go1.22.0 Mon Feb  5 12:34:56 UTC 2024\n\
+                   Use tags: with_gvisor, with_low_memory";
+        let info = parse_version_output(PathBuf::from("mihomo"), CoreSource::EnvPath, raw);
+
+        assert_eq!(info.version.as_deref(), Some("v1.18.1"));
+        assert_eq!(info.os.as_deref(), Some("linux"));
+        assert_eq!(info.arch.as_deref(), Some("amd64"));
+        assert_eq!(info.go_version.as_deref(), Some("go1.22.0"));
+        assert_eq!(
+            info.build_time.as_deref(),
+            Some("Mon Feb  5 12:34:56 UTC 2024")
+        );
+        assert_eq!(info.build_tags, vec!["with_gvisor", "with_low_memory"]);
+    }
+}
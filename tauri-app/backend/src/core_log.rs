@@ -0,0 +1,318 @@
+use crate::api_client::{LogMessage, MihomoApiClient};
+use crate::core_controller::wait_for_core;
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use tokio::sync::watch;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+/// 内存中保留的日志条数
+const RING_CAPACITY: usize = 2000;
+
+/// 单个日志文件的最大字节数，超过后轮转
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// 保留的历史日志文件数量（mihomo-core.log.1 ~ .N）
+const MAX_LOG_FILES: usize = 5;
+
+const LOG_FILE_NAME: &str = "mihomo-core.log";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// /logs 订阅是否已连接，连接期间进程标准输出不再重复进入日志视图
+static API_STREAM_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// mihomo 日志级别，按严重程度排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Silent,
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warning" | "warn" => Some(LogLevel::Warning),
+            "error" | "err" | "fatal" => Some(LogLevel::Error),
+            "silent" => Some(LogLevel::Silent),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Silent => "silent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreLogEntry {
+    pub level: LogLevel,
+    pub payload: String,
+    pub timestamp: String,
+}
+
+impl CoreLogEntry {
+    pub fn new(level: LogLevel, payload: String) -> Self {
+        Self {
+            level,
+            payload,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        }
+    }
+}
+
+/// 按大小轮转的日志文件
+struct RotatingLogWriter {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingLogWriter {
+    fn open(dir: PathBuf, file_name: &str) -> Result<Self> {
+        let path = dir.join(file_name);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self { path, file, size })
+    }
+
+    /// 写入一行（自动追加换行），超过大小限制时先轮转
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        let line = format!("{}\n", line);
+
+        if self.size + line.len() as u64 > MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+
+        self.file
+            .write_all(line.as_bytes())
+            .context("Failed to write log file")?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = |n: usize| self.path.with_extension(format!("log.{}", n));
+
+        let _ = std::fs::remove_file(rotated(MAX_LOG_FILES));
+        for n in (1..MAX_LOG_FILES).rev() {
+            let from = rotated(n);
+            if from.exists() {
+                std::fs::rename(&from, rotated(n + 1)).context("Failed to rotate log file")?;
+            }
+        }
+        std::fs::rename(&self.path, rotated(1)).context("Failed to rotate log file")?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to reopen log file")?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// 在专用线程中写入的轮转日志文件；调用方只把行送入通道，写文件和轮转不会阻塞异步运行时
+pub struct BackgroundLogWriter {
+    path: PathBuf,
+    lines: mpsc::Sender<String>,
+}
+
+impl BackgroundLogWriter {
+    pub fn open(dir: PathBuf, file_name: &str) -> Result<Self> {
+        let mut writer = RotatingLogWriter::open(dir, file_name)?;
+        let path = writer.path.clone();
+        let (lines, received) = mpsc::channel::<String>();
+
+        std::thread::Builder::new()
+            .name(format!("log-writer-{}", file_name))
+            .spawn(move || {
+                // 所有发送端释放后退出
+                for line in received {
+                    if let Err(e) = writer.write_line(&line) {
+                        warn!("Failed to write {}: {}", writer.path.display(), e);
+                    }
+                }
+            })
+            .context("Failed to start log writer thread")?;
+
+        Ok(Self { path, lines })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn write_line(&self, line: String) {
+        let _ = self.lines.send(line);
+    }
+}
+
+struct CoreLogStore {
+    entries: Mutex<VecDeque<CoreLogEntry>>,
+    writer: Mutex<Option<BackgroundLogWriter>>,
+    app_handle: Mutex<Option<tauri::AppHandle>>,
+}
+
+lazy_static! {
+    static ref CORE_LOGS: CoreLogStore = CoreLogStore {
+        entries: Mutex::new(VecDeque::with_capacity(RING_CAPACITY)),
+        writer: Mutex::new(None),
+        app_handle: Mutex::new(None),
+    };
+}
+
+/// 初始化日志子系统：打开日志文件并记录用于发送事件的 AppHandle
+pub fn init(app_handle: tauri::AppHandle) {
+    match crate::platform_config::PlatformPaths::log_dir()
+        .and_then(|dir| BackgroundLogWriter::open(dir, LOG_FILE_NAME))
+    {
+        Ok(writer) => {
+            info!("Core log file: {:?}", writer.path);
+            *CORE_LOGS.writer.lock().unwrap() = Some(writer);
+        }
+        Err(e) => warn!("Core log file disabled: {}", e),
+    }
+
+    *CORE_LOGS.app_handle.lock().unwrap() = Some(app_handle);
+}
+
+/// 记录一条核心日志：写入环形缓冲区、日志文件，并发送 core-log 事件
+pub fn record(entry: CoreLogEntry) {
+    {
+        let mut entries = CORE_LOGS.entries.lock().unwrap();
+        if entries.len() >= RING_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry.clone());
+    }
+
+    if let Some(writer) = CORE_LOGS.writer.lock().unwrap().as_ref() {
+        writer.write_line(format!(
+            "{} [{}] {}",
+            entry.timestamp,
+            entry.level.as_str(),
+            entry.payload
+        ));
+    }
+
+    let app_handle = CORE_LOGS.app_handle.lock().unwrap().clone();
+    if let Some(app_handle) = app_handle {
+        crate::events::emit_core_log(&app_handle, entry);
+    }
+}
+
+/// /logs 订阅当前是否在接收日志
+pub fn api_stream_connected() -> bool {
+    API_STREAM_CONNECTED.load(Ordering::Relaxed)
+}
+
+/// 返回最近的日志（按时间正序），可按最低级别过滤
+pub fn recent(min_level: Option<LogLevel>, limit: usize) -> Vec<CoreLogEntry> {
+    let entries = CORE_LOGS.entries.lock().unwrap();
+    let mut result: Vec<CoreLogEntry> = entries
+        .iter()
+        .rev()
+        .filter(|entry| min_level.is_none_or(|level| entry.level >= level))
+        .take(limit)
+        .cloned()
+        .collect();
+    result.reverse();
+    result
+}
+
+/// 启动 /logs 订阅任务，核心停止时暂停，恢复后自动重连
+pub fn spawn_log_stream(mut core_status: watch::Receiver<bool>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !wait_for_core(&mut core_status, true).await {
+                return;
+            }
+
+            tokio::select! {
+                result = stream_logs() => {
+                    if let Err(e) = result {
+                        warn!("Core log stream interrupted: {}", e);
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+                _ = wait_for_core(&mut core_status, false) => {
+                    info!("Mihomo is down, core log stream paused");
+                }
+            }
+        }
+    });
+}
+
+async fn stream_logs() -> Result<()> {
+    let level = configured_log_level().await;
+    let client = MihomoApiClient::from_active_config().await?;
+    let mut lines = client
+        .stream_lines(&format!("/logs?level={}", level.as_str()))
+        .await?;
+
+    info!("Core log stream connected (level: {})", level.as_str());
+
+    // 任务被取消或流结束时自动清除连接标记
+    struct ConnectedGuard;
+    impl Drop for ConnectedGuard {
+        fn drop(&mut self) {
+            API_STREAM_CONNECTED.store(false, Ordering::Relaxed);
+        }
+    }
+    API_STREAM_CONNECTED.store(true, Ordering::Relaxed);
+    let _connected = ConnectedGuard;
+
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str::<LogMessage>(&line) {
+            Ok(message) => record(CoreLogEntry::new(
+                LogLevel::parse(&message.r#type).unwrap_or(LogLevel::Info),
+                message.payload,
+            )),
+            Err(_) => record(CoreLogEntry::new(LogLevel::Info, line)),
+        }
+    }
+
+    Err(anyhow::anyhow!("Core log stream closed by mihomo"))
+}
+
+/// 订阅级别跟随配置中的 log-level
+async fn configured_log_level() -> LogLevel {
+    let config = match crate::config_manager::get_config_manager().await {
+        Ok(manager) => manager.read_config().await.ok(),
+        Err(_) => None,
+    };
+
+    config
+        .as_ref()
+        .and_then(|c| c.get("log-level"))
+        .and_then(|v| v.as_str())
+        .and_then(LogLevel::parse)
+        .filter(|level| *level != LogLevel::Silent)
+        .unwrap_or(LogLevel::Info)
+}
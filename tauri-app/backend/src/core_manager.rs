@@ -0,0 +1,475 @@
+use crate::core_locator::{self, CoreInfo};
+use crate::platform_config::{PathResolver, PlatformConfig, PlatformPaths};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+const CORES_DIR_NAME: &str = "cores";
+const CORE_META_FILE_NAME: &str = "core.json";
+
+/// 下载核心压缩包的超时时间
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 已安装版本的元数据，保存在版本目录下
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CoreMeta {
+    sha256: String,
+    source: String,
+    installed_at: String,
+}
+
+/// 由应用管理的核心版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledCore {
+    pub version: String,
+    pub path: PathBuf,
+    pub sha256: String,
+    /// 下载地址或本地压缩包路径
+    pub source: String,
+    pub installed_at: String,
+    pub active: bool,
+}
+
+/// 切换核心的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchResult {
+    pub success: bool,
+    /// 新核心未通过就绪检查，已回滚到之前的核心
+    pub rolled_back: bool,
+    pub message: String,
+    pub core: Option<CoreInfo>,
+    /// 重启后新进程的 PID
+    pub process_id: Option<u32>,
+}
+
+/// 核心版本目录：<配置目录>/cores/<version>
+pub fn cores_dir() -> Result<PathBuf> {
+    let dir = PlatformPaths::config_dir()?.join(CORES_DIR_NAME);
+    PathResolver::ensure_dir(&dir)?;
+    Ok(dir)
+}
+
+/// 列出已安装的核心版本
+pub async fn list_installed() -> Result<Vec<InstalledCore>> {
+    let active = core_locator::user_override().await?;
+    blocking(move || scan_installed(active)).await
+}
+
+fn scan_installed(active: Option<PathBuf>) -> Result<Vec<InstalledCore>> {
+    let binary_name = PlatformConfig::current_platform()?.mihomo_binary;
+
+    let mut cores = Vec::new();
+    for entry in std::fs::read_dir(cores_dir()?).context("Failed to read cores directory")? {
+        let entry = entry?;
+        let version = entry.file_name().to_string_lossy().to_string();
+        // 跳过安装中的临时目录
+        if version.starts_with('.') || !entry.path().is_dir() {
+            continue;
+        }
+
+        let meta = match read_meta(&entry.path()) {
+            Ok(meta) => meta,
+            Err(e) => {
+                warn!("Skipping core {} without valid metadata: {}", version, e);
+                continue;
+            }
+        };
+
+        let path = entry.path().join(&binary_name);
+        cores.push(InstalledCore {
+            active: active.as_deref() == Some(path.as_path()),
+            version,
+            path,
+            sha256: meta.sha256,
+            source: meta.source,
+            installed_at: meta.installed_at,
+        });
+    }
+
+    cores.sort_by(|a, b| b.installed_at.cmp(&a.installed_at));
+    Ok(cores)
+}
+
+fn read_meta(dir: &Path) -> Result<CoreMeta> {
+    let content = std::fs::read_to_string(dir.join(CORE_META_FILE_NAME))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// 下载并安装指定版本，`url` 为空时使用平台配置中的下载地址模板
+pub async fn install_from_url(
+    version: &str,
+    url: Option<&str>,
+    sha256: &str,
+) -> Result<InstalledCore> {
+    validate_version(version)?;
+
+    let url = match url {
+        Some(url) => url.to_string(),
+        None => download_url(&PlatformConfig::common()?.core_download_url, version),
+    };
+    info!("Downloading mihomo {} from {}", version, url);
+
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let bytes = client
+        .get(&url)
+        .send()
+        .await
+        .context("Failed to download core")?
+        .error_for_status()
+        .context("Failed to download core")?
+        .bytes()
+        .await
+        .context("Failed to read core archive")?;
+
+    // 去掉查询参数，按文件名判断压缩格式
+    let archive_name = url.split(['?', '#']).next().unwrap_or(&url);
+    install_archive(version, bytes.to_vec(), archive_name, sha256, url.clone()).await
+}
+
+/// 从本地压缩包安装指定版本
+pub async fn install_from_file(
+    version: &str,
+    archive: &Path,
+    sha256: &str,
+) -> Result<InstalledCore> {
+    validate_version(version)?;
+
+    let bytes = tokio::fs::read(archive)
+        .await
+        .with_context(|| format!("Failed to read {}", archive.display()))?;
+    let archive_name = archive.to_string_lossy().to_string();
+    install_archive(version, bytes, &archive_name, sha256, archive_name.clone()).await
+}
+
+async fn install_archive(
+    version: &str,
+    bytes: Vec<u8>,
+    archive_name: &str,
+    sha256: &str,
+    source: String,
+) -> Result<InstalledCore> {
+    let binary_name = PlatformConfig::current_platform()?.mihomo_binary;
+
+    // 先在临时目录中解压并验证，最后整体重命名，避免留下不完整的版本
+    let (actual, target_dir, tmp_dir) = {
+        let version = version.to_string();
+        let archive_name = archive_name.to_string();
+        let sha256 = sha256.trim().to_string();
+        let binary_name = binary_name.clone();
+        blocking(move || {
+            let actual = hex::encode(Sha256::digest(&bytes));
+            if !actual.eq_ignore_ascii_case(&sha256) {
+                anyhow::bail!(
+                    "SHA-256 mismatch for {}: expected {}, got {}",
+                    archive_name,
+                    sha256,
+                    actual
+                );
+            }
+
+            let target_dir = cores_dir()?.join(&version);
+            if target_dir.exists() {
+                anyhow::bail!("Core {} is already installed", version);
+            }
+
+            let binary = extract_core(&bytes, &archive_name)?;
+            let tmp_dir = cores_dir()?.join(format!(".{}.{}.tmp", version, uuid::Uuid::new_v4()));
+            if let Err(e) = write_binary(&tmp_dir.join(&binary_name), &binary) {
+                let _ = std::fs::remove_dir_all(&tmp_dir);
+                return Err(e);
+            }
+            Ok((actual, target_dir, tmp_dir))
+        })
+        .await?
+    };
+
+    let result = async {
+        core_locator::read_version(&tmp_dir.join(&binary_name))
+            .await
+            .context("Downloaded core does not run on this system")?;
+
+        let meta = CoreMeta {
+            sha256: actual,
+            source,
+            installed_at: chrono::Local::now().to_rfc3339(),
+        };
+        let content = serde_json::to_string_pretty(&meta)?;
+        let (tmp_dir, target_dir) = (tmp_dir.clone(), target_dir.clone());
+        blocking(move || {
+            std::fs::write(tmp_dir.join(CORE_META_FILE_NAME), content)
+                .context("Failed to write core metadata")?;
+            std::fs::rename(&tmp_dir, &target_dir).context("Failed to install core")
+        })
+        .await?;
+        anyhow::Ok(meta)
+    }
+    .await;
+
+    let meta = match result {
+        Ok(meta) => meta,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+            return Err(e);
+        }
+    };
+
+    info!("Installed mihomo {} to {}", version, target_dir.display());
+    Ok(InstalledCore {
+        version: version.to_string(),
+        path: target_dir.join(&binary_name),
+        sha256: meta.sha256,
+        source: meta.source,
+        installed_at: meta.installed_at,
+        active: false,
+    })
+}
+
+/// 切换到已安装的版本；核心正在运行时会重启，未通过就绪检查则回滚
+pub async fn switch_to(version: &str) -> Result<SwitchResult> {
+    let core = list_installed()
+        .await?
+        .into_iter()
+        .find(|core| core.version == version)
+        .ok_or_else(|| anyhow::anyhow!("Core {} is not installed", version))?;
+
+    let was_running = crate::mihomo::is_mihomo_running().await;
+    core_locator::switch_user_override(Some(core.path.to_string_lossy().to_string())).await?;
+    info!("Switched mihomo core to {}", version);
+
+    if !was_running {
+        return Ok(SwitchResult {
+            success: true,
+            rolled_back: false,
+            message: format!("Switched to {}, it will be used on next start", version),
+            core: core_locator::detect().await.ok(),
+            process_id: None,
+        });
+    }
+
+    match crate::mihomo::start_mihomo().await {
+        Ok(pid) => {
+            crate::selection::restore().await;
+            Ok(SwitchResult {
+                success: true,
+                rolled_back: false,
+                message: format!("Mihomo restarted with core {}", version),
+                core: core_locator::detect().await.ok(),
+                process_id: Some(pid),
+            })
+        }
+        Err(e) => {
+            warn!("Core {} failed to start, rolling back: {}", version, e);
+            core_locator::rollback_user_override().await?;
+
+            let process_id = match crate::mihomo::start_mihomo().await {
+                Ok(pid) => {
+                    crate::selection::restore().await;
+                    Some(pid)
+                }
+                Err(restart_error) => {
+                    warn!("Failed to restart previous core: {}", restart_error);
+                    None
+                }
+            };
+
+            Ok(SwitchResult {
+                success: false,
+                rolled_back: true,
+                message: format!(
+                    "Core {} failed to start and was rolled back: {}",
+                    version, e
+                ),
+                core: core_locator::detect().await.ok(),
+                process_id,
+            })
+        }
+    }
+}
+
+/// 回到上一次切换前的核心，核心正在运行时会重启
+pub async fn rollback() -> Result<SwitchResult> {
+    let was_running = crate::mihomo::is_mihomo_running().await;
+    core_locator::rollback_user_override().await?;
+
+    let core = core_locator::detect().await.ok();
+    if !was_running {
+        return Ok(SwitchResult {
+            success: true,
+            rolled_back: true,
+            message: "Rolled back, the previous core will be used on next start".to_string(),
+            core,
+            process_id: None,
+        });
+    }
+
+    let result = crate::mihomo::start_mihomo().await;
+    if result.is_ok() {
+        crate::selection::restore().await;
+    }
+    Ok(SwitchResult {
+        success: result.is_ok(),
+        rolled_back: true,
+        message: match &result {
+            Ok(_) => "Mihomo restarted with the previous core".to_string(),
+            Err(e) => format!("Failed to restart mihomo after rollback: {}", e),
+        },
+        core,
+        process_id: result.ok(),
+    })
+}
+
+/// 删除已安装的版本，不能删除正在使用的核心
+pub async fn remove(version: &str) -> Result<()> {
+    validate_version(version)?;
+
+    let core = list_installed()
+        .await?
+        .into_iter()
+        .find(|core| core.version == version)
+        .ok_or_else(|| anyhow::anyhow!("Core {} is not installed", version))?;
+    if core.active {
+        anyhow::bail!("Core {} is in use, switch to another core first", version);
+    }
+
+    let version_dir = version.to_string();
+    blocking(move || {
+        std::fs::remove_dir_all(cores_dir()?.join(version_dir)).context("Failed to remove core")
+    })
+    .await?;
+    info!("Removed mihomo core {}", version);
+    Ok(())
+}
+
+/// 在临时目录中写入核心并设为可执行
+fn write_binary(path: &Path, binary: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create core directory")?;
+    }
+    std::fs::write(path, binary).context("Failed to write core binary")?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .context("Failed to make core executable")?;
+    }
+    Ok(())
+}
+
+/// 文件读写和解压在阻塞线程池中进行
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .context("Core install task failed")?
+}
+
+/// 版本号用作目录名，只允许字母、数字和 `.-_`
+fn validate_version(version: &str) -> Result<()> {
+    let valid = !version.is_empty()
+        && !version.starts_with('.')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        anyhow::bail!("Invalid core version: {}", version);
+    }
+    Ok(())
+}
+
+/// 用当前平台替换下载地址模板中的占位符
+fn download_url(template: &str, version: &str) -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    };
+    let ext = if cfg!(target_os = "windows") {
+        "zip"
+    } else {
+        "gz"
+    };
+
+    template
+        .replace("{version}", version)
+        .replace("{os}", os)
+        .replace("{arch}", arch)
+        .replace("{ext}", ext)
+}
+
+/// 从 `.gz` 或 `.zip` 压缩包中取出核心可执行文件
+fn extract_core(bytes: &[u8], archive_name: &str) -> Result<Vec<u8>> {
+    let mut binary = Vec::new();
+
+    if archive_name.ends_with(".gz") {
+        flate2::read::GzDecoder::new(bytes)
+            .read_to_end(&mut binary)
+            .context("Failed to decompress core archive")?;
+    } else if archive_name.ends_with(".zip") {
+        let mut archive =
+            zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open core archive")?;
+
+        let name = archive
+            .file_names()
+            .find(|name| {
+                let file_name = name.rsplit('/').next().unwrap_or(name);
+                file_name.starts_with("mihomo")
+                    && (file_name.ends_with(".exe") || !cfg!(target_os = "windows"))
+            })
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("No mihomo binary found in {}", archive_name))?;
+
+        archive
+            .by_name(&name)?
+            .read_to_end(&mut binary)
+            .context("Failed to extract core archive")?;
+    } else {
+        anyhow::bail!("Unsupported core archive: {}", archive_name);
+    }
+
+    if binary.is_empty() {
+        anyhow::bail!("Core archive {} is empty", archive_name);
+    }
+    Ok(binary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_extract_core() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"binary").unwrap();
+        let gz = encoder.finish().unwrap();
+        assert_eq!(
+            extract_core(&gz, "mihomo-linux-amd64-v1.0.0.gz").unwrap(),
+            b"binary"
+        );
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        zip.start_file("README.md", options).unwrap();
+        zip.write_all(b"readme").unwrap();
+        zip.start_file("mihomo-windows-amd64.exe", options).unwrap();
+        zip.write_all(b"binary").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+        assert_eq!(extract_core(&zip, "core.zip").unwrap(), b"binary");
+
+        assert!(extract_core(b"data", "core.tar").is_err());
+        assert!(validate_version("../v1").is_err());
+        assert!(validate_version("v1.18.1").is_ok());
+    }
+}
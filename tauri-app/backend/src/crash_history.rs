@@ -0,0 +1,159 @@
+use crate::process_output;
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+const HISTORY_FILE_NAME: &str = "crash-history.json";
+
+/// 保留的崩溃记录数
+const MAX_RECORDS: usize = 50;
+
+/// API 无响应后等待进程退出、读取退出码的最长时间
+const EXIT_WAIT: Duration = Duration::from_secs(1);
+
+/// 一次核心崩溃
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashRecord {
+    pub timestamp: u64,
+    pub reason: String,
+    pub process_id: Option<u32>,
+    /// 仅当核心由本应用启动且已退出时可用
+    pub exit_code: Option<i32>,
+    /// 退出状态的描述，包括 Unix 上的终止信号
+    pub exit_status: Option<String>,
+    /// 崩溃前的运行时长（秒）
+    pub uptime_secs: Option<u64>,
+    /// 崩溃前最后的 stderr 输出
+    pub stderr: Vec<String>,
+    /// 核心启动时配置文件的 SHA-256
+    pub config_hash: Option<String>,
+    pub core_path: Option<String>,
+}
+
+lazy_static! {
+    // 首次使用时从文件加载
+    static ref HISTORY: Mutex<Option<VecDeque<CrashRecord>>> = Mutex::new(None);
+    // 核心进入 Running 时配置文件的哈希，即崩溃时实际生效的配置
+    static ref CONFIG_IN_EFFECT: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// 核心启动或重新加载配置后调用，记录当前生效配置的哈希
+pub async fn capture_config() {
+    let hash = config_hash().await;
+    *CONFIG_IN_EFFECT.lock().unwrap() = hash;
+}
+
+/// 记录一次崩溃；`running_since` 为核心进入 Running 的时间，用于估算非本应用启动的核心的运行时长
+pub async fn record(reason: &str, running_since: u64) {
+    let exit = crate::process_manager::reap(EXIT_WAIT).await;
+    let now = crate::events::get_current_timestamp();
+    let config_in_effect = CONFIG_IN_EFFECT.lock().unwrap().clone();
+
+    let record = CrashRecord {
+        timestamp: now,
+        reason: reason.to_string(),
+        process_id: exit.as_ref().map(|e| e.pid),
+        exit_code: exit.as_ref().and_then(|e| e.status?.code()),
+        exit_status: exit
+            .as_ref()
+            .and_then(|e| e.status)
+            .map(|status| status.to_string()),
+        uptime_secs: Some(match &exit {
+            Some(exit) => exit.uptime.as_secs(),
+            None => now.saturating_sub(running_since),
+        }),
+        stderr: process_output::recent_stderr(),
+        config_hash: match config_in_effect {
+            Some(hash) => Some(hash),
+            None => config_hash().await,
+        },
+        core_path: match &exit {
+            Some(exit) => Some(exit.exe.display().to_string()),
+            None => crate::core_locator::locate()
+                .await
+                .ok()
+                .map(|path| path.display().to_string()),
+        },
+    };
+
+    info!(
+        "Recorded mihomo crash: {} (exit: {})",
+        record.reason,
+        record.exit_status.as_deref().unwrap_or("unknown")
+    );
+
+    // 文件读写在阻塞线程池中进行
+    let saved = tokio::task::spawn_blocking(move || {
+        let mut history = HISTORY.lock().unwrap();
+        let records = history.get_or_insert_with(load_history);
+        if records.len() >= MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+        save_history(records)
+    })
+    .await;
+
+    match saved {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Failed to save crash history: {}", e),
+        Err(e) => warn!("Failed to save crash history: {}", e),
+    }
+}
+
+/// 崩溃记录，最新的在前
+pub async fn history() -> Vec<CrashRecord> {
+    tokio::task::spawn_blocking(|| {
+        let mut history = HISTORY.lock().unwrap();
+        history
+            .get_or_insert_with(load_history)
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+async fn config_hash() -> Option<String> {
+    let path = crate::platform_config::PlatformPaths::config_file().ok()?;
+    let content = tokio::fs::read(path).await.ok()?;
+    Some(hex::encode(Sha256::digest(content)))
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(crate::platform_config::PlatformPaths::log_dir()?.join(HISTORY_FILE_NAME))
+}
+
+fn load_history() -> VecDeque<CrashRecord> {
+    let load = || -> Result<VecDeque<CrashRecord>> {
+        let path = history_path()?;
+        if !path.exists() {
+            return Ok(VecDeque::new());
+        }
+        let content = std::fs::read_to_string(&path).context("Failed to read crash history")?;
+        serde_json::from_str(&content).context("Failed to parse crash history")
+    };
+
+    load().unwrap_or_else(|e| {
+        warn!("Crash history unavailable: {}", e);
+        VecDeque::new()
+    })
+}
+
+fn save_history(records: &VecDeque<CrashRecord>) -> Result<()> {
+    let content =
+        serde_json::to_string_pretty(records).context("Failed to serialize crash history")?;
+
+    let path = history_path()?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, content).context("Failed to write crash history")?;
+    std::fs::rename(&tmp_path, &path).context("Failed to replace crash history")
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, Instant};
+
+/// 经由代理端口的数据通路健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataPathState {
+    /// 未启用深度探测
+    Disabled,
+    /// 尚未探测（核心刚启动或未运行）
+    Unknown,
+    Healthy,
+    /// 连续探测失败达到阈值
+    Failing,
+}
+
+/// 最近一次数据通路探测结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataPathStatus {
+    pub state: DataPathState,
+    pub latency_ms: Option<u32>,
+    pub error: Option<String>,
+    /// 连续失败次数
+    pub failures: u32,
+}
+
+impl DataPathStatus {
+    pub fn new(state: DataPathState) -> Self {
+        Self {
+            state,
+            latency_ms: None,
+            error: None,
+            failures: 0,
+        }
+    }
+}
+
+/// 探测使用的本地代理入口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyEndpoint {
+    pub port: u16,
+    /// 配置了 authentication 时使用第一组用户名和密码
+    pub credentials: Option<(String, String)>,
+}
+
+/// 读取配置中的代理端口（优先 mixed-port）和认证信息
+pub async fn proxy_endpoint() -> Result<ProxyEndpoint> {
+    let config = crate::config::load_config().await?;
+    endpoint_from_config(&config)
+}
+
+fn endpoint_from_config(config: &serde_json::Value) -> Result<ProxyEndpoint> {
+    let port = ["mixed-port", "port"]
+        .iter()
+        .filter_map(|key| config.get(*key).and_then(|v| v.as_u64()))
+        .find(|port| *port > 0)
+        .and_then(|port| u16::try_from(port).ok())
+        .ok_or_else(|| anyhow::anyhow!("No mixed-port or port configured"))?;
+
+    // 格式为 "user:pass"
+    let credentials = config
+        .get("authentication")
+        .and_then(|v| v.as_array())
+        .and_then(|entries| entries.iter().find_map(|entry| entry.as_str()))
+        .and_then(|entry| entry.split_once(':'))
+        .map(|(user, pass)| (user.to_string(), pass.to_string()));
+
+    Ok(ProxyEndpoint { port, credentials })
+}
+
+/// 通过本地代理端口请求 `url`，返回耗时（毫秒）；HTTP 错误状态视为失败
+pub async fn probe(endpoint: &ProxyEndpoint, url: &str, timeout: Duration) -> Result<u32> {
+    let port = endpoint.port;
+    let mut proxy = reqwest::Proxy::all(format!("http://127.0.0.1:{}", port))
+        .context("Invalid proxy address")?;
+    if let Some((user, pass)) = &endpoint.credentials {
+        proxy = proxy.basic_auth(user, pass);
+    }
+    let client = reqwest::Client::builder()
+        .proxy(proxy)
+        .timeout(timeout)
+        .build()
+        .context("Failed to build probe client")?;
+
+    let started = Instant::now();
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Probe request to {} via port {} failed", url, port))?;
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        anyhow::bail!("Probe {} returned {}", url, status);
+    }
+
+    Ok(started.elapsed().as_millis().min(u32::MAX as u128) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 充当代理端口的本地桩服务；`require_auth` 时没有 Proxy-Authorization 的请求返回 407
+    async fn stub_proxy(status_line: &'static str, require_auth: bool) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = [0u8; 1024];
+                let read = socket.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+                let status_line = if require_auth && !request.contains("proxy-authorization: basic")
+                {
+                    "HTTP/1.1 407 Proxy Authentication Required"
+                } else {
+                    status_line
+                };
+                let response = format!("{}\r\nContent-Length: 0\r\n\r\n", status_line);
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        port
+    }
+
+    fn endpoint(port: u16) -> ProxyEndpoint {
+        ProxyEndpoint {
+            port,
+            credentials: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_through_stub_proxy() {
+        let timeout = Duration::from_secs(5);
+        let url = "http://probe.invalid/generate_204";
+
+        let port = stub_proxy("HTTP/1.1 204 No Content", false).await;
+        assert!(probe(&endpoint(port), url, timeout).await.is_ok());
+
+        let port = stub_proxy("HTTP/1.1 502 Bad Gateway", false).await;
+        assert!(probe(&endpoint(port), url, timeout).await.is_err());
+
+        // 配置了 authentication 的代理端口
+        let config = serde_json::json!({
+            "mixed-port": 0,
+            "port": 7890,
+            "authentication": ["user:p:ss"],
+        });
+        let configured = endpoint_from_config(&config).unwrap();
+        assert_eq!(configured.port, 7890);
+        assert_eq!(
+            configured.credentials,
+            Some(("user".to_string(), "p:ss".to_string()))
+        );
+
+        let port = stub_proxy("HTTP/1.1 204 No Content", true).await;
+        assert!(probe(&endpoint(port), url, timeout).await.is_err());
+        let authenticated = ProxyEndpoint { port, ..configured };
+        assert!(probe(&authenticated, url, timeout).await.is_ok());
+
+        // 没有监听的端口
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = listener.local_addr().unwrap().port();
+        drop(listener);
+        assert!(probe(&endpoint(closed), url, timeout).await.is_err());
+    }
+}
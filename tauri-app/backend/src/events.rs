@@ -1,3 +1,4 @@
+use crate::core_controller::CoreState;
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
 
@@ -5,6 +6,10 @@ use tauri::Manager;
 pub struct MihomoStatusEvent {
     pub running: bool,
     pub process_id: Option<u32>,
+    pub state: CoreState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub data_path: crate::data_path::DataPathStatus,
     pub timestamp: u64,
 }
 
@@ -18,6 +23,62 @@ pub struct ConfigChangeEvent {
 pub struct ProxyChangeEvent {
     pub group_name: String,
     pub proxy_name: String,
+    pub reason: ProxyChangeReason,
+    pub timestamp: u64,
+}
+
+/// 切换节点的原因
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyChangeReason {
+    /// 用户手动切换
+    Manual,
+    /// 当前节点连续测试失败，自动切换到备选节点
+    Failover,
+    /// 原节点恢复，自动切换回去
+    Recovered,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TrafficEvent {
+    pub up: u64,
+    pub down: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ConnectionsEvent {
+    pub snapshot: crate::api_client::ConnectionsSnapshot,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ModeChangeEvent {
+    pub mode: Option<crate::api_client::ProxyMode>,
+    pub log_level: Option<crate::core_log::LogLevel>,
+    pub allow_lan: Option<bool>,
+    pub persisted: bool,
+    pub timestamp: u64,
+}
+
+/// 批量测速中单个节点完成
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DelayProgressEvent {
+    pub name: String,
+    pub delay: Option<u32>,
+    pub error: Option<String>,
+    pub completed: usize,
+    pub total: usize,
+    pub timestamp: u64,
+}
+
+/// watchdog 达到最大重启次数，停止自动重启
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct WatchdogGaveUpEvent {
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// 距离下一轮自动重启的秒数，None 表示不再自动重试
+    pub retry_in_secs: Option<u64>,
     pub timestamp: u64,
 }
 
@@ -39,6 +100,42 @@ pub fn emit_proxy_change(app: &tauri::AppHandle, event: ProxyChangeEvent) {
     }
 }
 
+pub fn emit_traffic_update(app: &tauri::AppHandle, event: TrafficEvent) {
+    if let Err(e) = app.emit_all("traffic-update", event) {
+        eprintln!("Failed to emit traffic-update event: {}", e);
+    }
+}
+
+pub fn emit_core_log(app: &tauri::AppHandle, entry: crate::core_log::CoreLogEntry) {
+    if let Err(e) = app.emit_all("core-log", entry) {
+        eprintln!("Failed to emit core-log event: {}", e);
+    }
+}
+
+pub fn emit_connections_update(app: &tauri::AppHandle, event: ConnectionsEvent) {
+    if let Err(e) = app.emit_all("connections-update", event) {
+        eprintln!("Failed to emit connections-update event: {}", e);
+    }
+}
+
+pub fn emit_mode_change(app: &tauri::AppHandle, event: ModeChangeEvent) {
+    if let Err(e) = app.emit_all("mode-change", event) {
+        eprintln!("Failed to emit mode-change event: {}", e);
+    }
+}
+
+pub fn emit_delay_progress(app: &tauri::AppHandle, event: DelayProgressEvent) {
+    if let Err(e) = app.emit_all("delay-progress", event) {
+        eprintln!("Failed to emit delay-progress event: {}", e);
+    }
+}
+
+pub fn emit_watchdog_gave_up(app: &tauri::AppHandle, event: WatchdogGaveUpEvent) {
+    if let Err(e) = app.emit_all("watchdog-gave-up", event) {
+        eprintln!("Failed to emit watchdog-gave-up event: {}", e);
+    }
+}
+
 pub fn get_current_timestamp() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -0,0 +1,293 @@
+use crate::api_client::ProxyInfo;
+use crate::core_controller::{CoreController, CoreState};
+use crate::events::{ProxyChangeEvent, ProxyChangeReason};
+use crate::watchdog::WatchdogPolicy;
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+/// 同时测试的备选节点数
+const CANDIDATE_CONCURRENCY: usize = 8;
+
+/// select 代理组的自动故障转移策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailoverPolicy {
+    pub enabled: bool,
+    /// 需要故障转移的 select 代理组
+    pub groups: Vec<String>,
+    /// 检查间隔（秒）
+    pub interval_secs: u64,
+    /// 当前节点连续测试失败多少次后切换
+    pub failure_threshold: u32,
+    pub test_url: String,
+    /// 单个节点的测试超时（毫秒）
+    pub timeout_ms: u32,
+    /// 原节点恢复后是否切换回去
+    pub switch_back: bool,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            groups: vec!["PROXY".to_string()],
+            interval_secs: 30,
+            failure_threshold: 3,
+            test_url: crate::mihomo::DEFAULT_TEST_URL.to_string(),
+            timeout_ms: 5000,
+            switch_back: false,
+        }
+    }
+}
+
+impl FailoverPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interval_secs == 0 {
+            anyhow::bail!("failover interval_secs must be at least 1");
+        }
+        if self.failure_threshold == 0 {
+            anyhow::bail!("failover failure_threshold must be at least 1");
+        }
+        if self.timeout_ms == 0 {
+            anyhow::bail!("failover timeout_ms must be at least 1");
+        }
+        reqwest::Url::parse(&self.test_url)
+            .map_err(|e| anyhow::anyhow!("Invalid failover test_url: {}", e))?;
+        Ok(())
+    }
+}
+
+/// 单个代理组的故障转移状态
+#[derive(Debug, Default)]
+struct GroupState {
+    failures: u32,
+    /// 故障转移前用户选择的节点
+    original: Option<String>,
+    /// 故障转移切换到的节点；当前选择与之不同说明用户手动切换过
+    switched_to: Option<String>,
+}
+
+/// 在 watchdog 监控期间定期检查选定代理组的当前节点
+pub fn spawn(
+    app_handle: tauri::AppHandle,
+    controller: Arc<CoreController>,
+    policy: Arc<RwLock<WatchdogPolicy>>,
+    monitoring: Arc<RwLock<bool>>,
+    paused: Arc<RwLock<bool>>,
+) {
+    tokio::spawn(async move {
+        let mut groups: HashMap<String, GroupState> = HashMap::new();
+
+        loop {
+            let failover = policy.read().await.failover.clone();
+            tokio::time::sleep(Duration::from_secs(failover.interval_secs.max(1))).await;
+
+            if !*monitoring.read().await {
+                break;
+            }
+
+            // 核心重启后节点状态不再可信，重新开始
+            if !failover.enabled || *paused.read().await || controller.state() != CoreState::Running
+            {
+                groups.clear();
+                continue;
+            }
+
+            if let Err(e) = check_groups(&app_handle, &failover, &mut groups).await {
+                warn!("Failover check failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn check_groups(
+    app_handle: &tauri::AppHandle,
+    policy: &FailoverPolicy,
+    groups: &mut HashMap<String, GroupState>,
+) -> Result<()> {
+    let proxies = crate::mihomo::get_proxies().await?.proxies;
+    groups.retain(|name, _| policy.groups.contains(name));
+
+    for group_name in &policy.groups {
+        // 只有 select 类型的代理组支持手动选择
+        let Some(group) = proxies.get(group_name).filter(|g| g.r#type == "Selector") else {
+            continue;
+        };
+        let Some(current) = group.now.clone() else {
+            continue;
+        };
+
+        let state = groups.entry(group_name.clone()).or_default();
+        if state
+            .switched_to
+            .as_ref()
+            .is_some_and(|node| *node != current)
+        {
+            // 用户手动切换了节点，不再切换回原节点
+            *state = GroupState::default();
+        }
+
+        if policy.switch_back {
+            if let Some(original) = state.original.clone() {
+                if group.all.contains(&original) && test(policy, &original).await.is_ok() {
+                    switch(
+                        app_handle,
+                        group_name,
+                        &original,
+                        ProxyChangeReason::Recovered,
+                    )
+                    .await?;
+                    *state = GroupState::default();
+                    continue;
+                }
+            }
+        }
+
+        match test(policy, &current).await {
+            Ok(_) => {
+                state.failures = 0;
+                continue;
+            }
+            Err(e) => {
+                state.failures += 1;
+                warn!(
+                    "Failover: {} -> {} failed ({}/{}): {}",
+                    group_name, current, state.failures, policy.failure_threshold, e
+                );
+            }
+        }
+
+        if state.failures < policy.failure_threshold {
+            continue;
+        }
+
+        let candidates = candidates(&group.all, &current, &proxies);
+        let results = stream::iter(candidates)
+            .map(|name| async move {
+                let result = test(policy, &name).await;
+                (name, result)
+            })
+            .buffer_unordered(CANDIDATE_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        let Some(best) = pick_best(results) else {
+            warn!("Failover: no healthy alternative in group {}", group_name);
+            continue;
+        };
+
+        switch(app_handle, group_name, &best, ProxyChangeReason::Failover).await?;
+        state.original.get_or_insert(current);
+        state.switched_to = Some(best);
+        state.failures = 0;
+    }
+
+    Ok(())
+}
+
+/// 代理组中可切换到的真实节点，排除当前节点、DIRECT 等内置出站和其他代理组
+///
+/// 返回 Vec：借用迭代器放进 stream 会让 spawn 的 future 推断不出 Send。
+fn candidates(
+    members: &[String],
+    current: &str,
+    proxies: &HashMap<String, ProxyInfo>,
+) -> Vec<String> {
+    members
+        .iter()
+        .filter(|name| *name != current)
+        .filter(|name| proxies.get(*name).is_some_and(ProxyInfo::is_node))
+        .cloned()
+        .collect()
+}
+
+async fn test(policy: &FailoverPolicy, proxy_name: &str) -> Result<u32> {
+    crate::mihomo::test_proxy_delay(proxy_name, policy.timeout_ms, &policy.test_url).await
+}
+
+/// 延迟最低的可用节点
+fn pick_best(results: Vec<(String, Result<u32>)>) -> Option<String> {
+    results
+        .into_iter()
+        .filter_map(|(name, result)| result.ok().map(|delay| (delay, name)))
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// 切换节点但不记住选择，核心重启后仍恢复用户选择的节点
+async fn switch(
+    app_handle: &tauri::AppHandle,
+    group_name: &str,
+    proxy_name: &str,
+    reason: ProxyChangeReason,
+) -> Result<()> {
+    crate::mihomo::switch_proxy(group_name, proxy_name).await?;
+    info!(
+        "Failover: switched {} to {} ({:?})",
+        group_name, proxy_name, reason
+    );
+
+    crate::events::emit_proxy_change(
+        app_handle,
+        ProxyChangeEvent {
+            group_name: group_name.to_string(),
+            proxy_name: proxy_name.to_string(),
+            reason,
+            timestamp: crate::events::get_current_timestamp(),
+        },
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_best() {
+        let results = vec![
+            ("slow".to_string(), Ok(300)),
+            ("dead".to_string(), Err(anyhow::anyhow!("timeout"))),
+            ("fast".to_string(), Ok(80)),
+        ];
+        assert_eq!(pick_best(results).as_deref(), Some("fast"));
+
+        let results = vec![("dead".to_string(), Err(anyhow::anyhow!("timeout")))];
+        assert_eq!(pick_best(results), None);
+    }
+
+    #[test]
+    fn test_candidates_skip_builtins_and_groups() {
+        let proxies: HashMap<String, ProxyInfo> = [
+            ("hk", "Shadowsocks"),
+            ("jp", "Vmess"),
+            ("DIRECT", "Direct"),
+            ("REJECT", "Reject"),
+            ("auto", "URLTest"),
+        ]
+        .into_iter()
+        .map(|(name, kind)| {
+            let info =
+                serde_json::from_value(serde_json::json!({"name": name, "type": kind})).unwrap();
+            (name.to_string(), info)
+        })
+        .collect();
+
+        let members: Vec<String> = ["hk", "jp", "DIRECT", "REJECT", "auto", "missing"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        // DIRECT 测速总是最快，不能成为候选，否则流量会绕过代理
+        assert_eq!(candidates(&members, "hk", &proxies), vec!["jp"]);
+        assert_eq!(candidates(&members, "jp", &proxies), vec!["hk"]);
+    }
+}
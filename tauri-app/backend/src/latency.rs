@@ -0,0 +1,327 @@
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+const HISTORY_FILE_NAME: &str = "latency-history.jsonl";
+
+/// 样本保留时长
+const RETENTION_SECS: i64 = 30 * 24 * 3600;
+
+/// 每个节点最多保留的样本数
+const MAX_SAMPLES_PER_NODE: usize = 1000;
+
+/// 追加写入超过该行数后重写文件，清理过期样本
+const COMPACT_THRESHOLD: usize = 5000;
+
+pub const DEFAULT_WINDOW_HOURS: u32 = 24;
+
+/// 一次延迟测试的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencySample {
+    pub node: String,
+    /// 节点所属订阅 ID，手动添加的节点为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription: Option<String>,
+    /// Unix 时间戳（秒）
+    pub timestamp: i64,
+    /// 延迟（毫秒），测试失败为 None
+    pub delay: Option<u32>,
+}
+
+/// 节点在统计窗口内的质量指标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeStats {
+    pub name: String,
+    pub subscription: Option<String>,
+    pub window_hours: u32,
+    pub samples: usize,
+    pub success: usize,
+    pub success_rate: f64,
+    pub p50: Option<u32>,
+    pub p95: Option<u32>,
+    /// 相邻两次成功测试的延迟差的平均值（毫秒）
+    pub jitter: Option<f64>,
+    pub last_delay: Option<u32>,
+    pub last_tested: Option<i64>,
+}
+
+type NodeKey = (Option<String>, String);
+
+struct LatencyStore {
+    path: PathBuf,
+    samples: HashMap<NodeKey, Vec<LatencySample>>,
+    appended: usize,
+}
+
+impl LatencyStore {
+    fn open(path: PathBuf) -> Result<Self> {
+        let mut store = Self {
+            path,
+            samples: HashMap::new(),
+            appended: 0,
+        };
+
+        if store.path.exists() {
+            let content = fs::read_to_string(&store.path)
+                .with_context(|| format!("Failed to read {}", store.path.display()))?;
+
+            let mut lines = 0;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                lines += 1;
+                match serde_json::from_str::<LatencySample>(line) {
+                    Ok(sample) => store.insert(sample),
+                    Err(e) => warn!("Skipping malformed latency sample: {}", e),
+                }
+            }
+
+            store.prune();
+            if store.len() < lines {
+                store.compact()?;
+            }
+        }
+
+        info!("Latency history loaded: {} samples", store.len());
+        Ok(store)
+    }
+
+    fn len(&self) -> usize {
+        self.samples.values().map(Vec::len).sum()
+    }
+
+    fn insert(&mut self, sample: LatencySample) {
+        self.samples
+            .entry((sample.subscription.clone(), sample.node.clone()))
+            .or_default()
+            .push(sample);
+    }
+
+    fn append(&mut self, samples: Vec<LatencySample>) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+
+        let mut buffer = String::new();
+        for sample in &samples {
+            buffer.push_str(&serde_json::to_string(sample)?);
+            buffer.push('\n');
+        }
+        file.write_all(buffer.as_bytes())
+            .context("Failed to write latency history")?;
+
+        self.appended += samples.len();
+        for sample in samples {
+            self.insert(sample);
+        }
+
+        if self.appended >= COMPACT_THRESHOLD {
+            self.prune();
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// 删除过期样本，并限制每个节点的样本数
+    fn prune(&mut self) {
+        let cutoff = chrono::Utc::now().timestamp() - RETENTION_SECS;
+
+        self.samples.retain(|_, samples| {
+            samples.retain(|s| s.timestamp >= cutoff);
+            samples.sort_by_key(|s| s.timestamp);
+            if samples.len() > MAX_SAMPLES_PER_NODE {
+                samples.drain(..samples.len() - MAX_SAMPLES_PER_NODE);
+            }
+            !samples.is_empty()
+        });
+    }
+
+    /// 用内存中的样本重写历史文件
+    fn compact(&mut self) -> Result<()> {
+        let mut buffer = String::new();
+        for sample in self.samples.values().flatten() {
+            buffer.push_str(&serde_json::to_string(sample)?);
+            buffer.push('\n');
+        }
+
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp_path, buffer).context("Failed to write latency history")?;
+        fs::rename(&tmp_path, &self.path).context("Failed to replace latency history")?;
+
+        self.appended = 0;
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref LATENCY_STORE: Mutex<Option<LatencyStore>> = Mutex::new(None);
+}
+
+/// 首次使用时从磁盘加载历史；文件读写在阻塞线程池中进行
+async fn with_store<T: Send + 'static>(
+    f: impl FnOnce(&mut LatencyStore) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(move || {
+        let mut guard = LATENCY_STORE.lock().unwrap();
+        if guard.is_none() {
+            let path = crate::platform_config::PlatformPaths::config_dir()?.join(HISTORY_FILE_NAME);
+            *guard = Some(LatencyStore::open(path)?);
+        }
+        f(guard.as_mut().unwrap())
+    })
+    .await
+    .context("Latency history task failed")?
+}
+
+/// 记录一批测速结果，节点所属订阅由最近一次生成配置时的映射确定
+pub async fn record_results(results: &HashMap<String, Option<u32>>) {
+    if results.is_empty() {
+        return;
+    }
+
+    let sources = crate::subscription::node_sources().await;
+    let timestamp = chrono::Utc::now().timestamp();
+    let samples = results
+        .iter()
+        .map(|(node, delay)| LatencySample {
+            node: node.clone(),
+            subscription: sources.get(node).cloned(),
+            timestamp,
+            delay: *delay,
+        })
+        .collect();
+
+    if let Err(e) = with_store(|store| store.append(samples)).await {
+        warn!("Failed to record latency history: {}", e);
+    }
+}
+
+/// 统计节点最近 `window_hours` 小时的测速结果，未指定订阅时合并所有同名节点
+pub async fn node_stats(
+    name: &str,
+    subscription: Option<&str>,
+    window_hours: u32,
+) -> Result<NodeStats> {
+    let cutoff = chrono::Utc::now().timestamp() - window_hours as i64 * 3600;
+
+    let node_name = name.to_string();
+    let node_subscription = subscription.map(str::to_string);
+    let mut samples: Vec<LatencySample> = with_store(move |store| {
+        Ok(store
+            .samples
+            .iter()
+            .filter(|((sub, node), _)| {
+                *node == node_name
+                    && node_subscription
+                        .as_deref()
+                        .is_none_or(|s| sub.as_deref() == Some(s))
+            })
+            .flat_map(|(_, samples)| samples.iter())
+            .filter(|s| s.timestamp >= cutoff)
+            .cloned()
+            .collect())
+    })
+    .await?;
+    samples.sort_by_key(|s| s.timestamp);
+
+    Ok(compute_stats(
+        name,
+        subscription.map(str::to_string),
+        window_hours,
+        &samples,
+    ))
+}
+
+/// 样本需按时间正序排列
+fn compute_stats(
+    name: &str,
+    subscription: Option<String>,
+    window_hours: u32,
+    samples: &[LatencySample],
+) -> NodeStats {
+    let delays: Vec<u32> = samples.iter().filter_map(|s| s.delay).collect();
+
+    let jitter = (delays.len() >= 2).then(|| {
+        let total: u64 = delays.windows(2).map(|w| w[0].abs_diff(w[1]) as u64).sum();
+        total as f64 / (delays.len() - 1) as f64
+    });
+
+    let mut sorted = delays.clone();
+    sorted.sort_unstable();
+
+    let last = samples.last();
+
+    NodeStats {
+        name: name.to_string(),
+        subscription,
+        window_hours,
+        samples: samples.len(),
+        success: delays.len(),
+        success_rate: if samples.is_empty() {
+            0.0
+        } else {
+            delays.len() as f64 / samples.len() as f64
+        },
+        p50: percentile(&sorted, 50),
+        p95: percentile(&sorted, 95),
+        jitter,
+        last_delay: last.and_then(|s| s.delay),
+        last_tested: last.map(|s| s.timestamp),
+    }
+}
+
+/// 最近秩法计算百分位数，`sorted` 需升序
+fn percentile(sorted: &[u32], p: usize) -> Option<u32> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, delay: Option<u32>) -> LatencySample {
+        LatencySample {
+            node: "node".to_string(),
+            subscription: None,
+            timestamp,
+            delay,
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let samples = vec![
+            sample(1, Some(100)),
+            sample(2, None),
+            sample(3, Some(140)),
+            sample(4, Some(120)),
+            sample(5, Some(400)),
+        ];
+
+        let stats = compute_stats("node", None, 24, &samples);
+        assert_eq!(stats.samples, 5);
+        assert_eq!(stats.success, 4);
+        assert!((stats.success_rate - 0.8).abs() < f64::EPSILON);
+        assert_eq!(stats.p50, Some(120));
+        assert_eq!(stats.p95, Some(400));
+        // |140-100| + |120-140| + |400-120| = 340，共 3 个差值
+        assert!((stats.jitter.unwrap() - 340.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.last_delay, Some(400));
+        assert_eq!(stats.last_tested, Some(5));
+
+        let empty = compute_stats("node", None, 24, &[]);
+        assert_eq!(empty.p50, None);
+        assert_eq!(empty.jitter, None);
+        assert_eq!(empty.success_rate, 0.0);
+    }
+}
@@ -1,33 +1,37 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod api_client;
 mod backup;
 mod config;
 mod config_manager;
+mod connections;
+mod core_controller;
+mod core_locator;
+mod core_log;
+mod core_manager;
+mod crash_history;
+mod data_path;
 mod error;
 mod events;
+mod failover;
+mod latency;
 mod mihomo;
 mod platform_config;
+mod process_manager;
+mod process_output;
+mod selection;
 mod subscription;
+mod traffic;
 mod validator;
 mod watchdog;
+mod yaml_edit;
 
-use serde::{Deserialize, Serialize};
 use tauri::{
-    CustomMenuItem, Manager, State, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+    CustomMenuItem, Manager, State, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem, SystemTraySubmenu,
 };
 
-lazy_static::lazy_static! {
-    // 防止并发启动的全局锁
-    static ref SERVICE_START_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct AppState {
-    pub mihomo_running: bool,
-    pub mihomo_process: Option<u32>,
-}
-
 fn resolve_app_dir() -> Result<std::path::PathBuf, String> {
     std::env::current_exe()
         .map_err(|e| format!("获取应用目录失败: {}", e))?
@@ -51,27 +55,6 @@ fn resolve_winsw_source(app_dir: &std::path::Path) -> Result<std::path::PathBuf,
     ))
 }
 
-fn resolve_mihomo_path(app_dir: &std::path::Path) -> Result<std::path::PathBuf, String> {
-    let mihomo = app_dir.join("mihomo.exe");
-    if mihomo.exists() {
-        return Ok(mihomo);
-    }
-
-    // 尝试从 resources 目录复制到应用目录
-    let resource = app_dir.join("resources").join("mihomo.exe");
-    if resource.exists() {
-        std::fs::copy(&resource, &mihomo).map_err(|e| format!("复制 mihomo.exe 失败: {}", e))?;
-        if mihomo.exists() {
-            return Ok(mihomo);
-        }
-    }
-
-    Err(format!(
-        "未找到 mihomo.exe 文件。期望位置: {}",
-        mihomo.display()
-    ))
-}
-
 fn ensure_winsw_files(
     app_dir: &std::path::Path,
     winsw_source: &std::path::Path,
@@ -137,82 +120,52 @@ async fn get_bundled_winsw_path() -> Result<String, String> {
     ))
 }
 
-type AppStateType = tokio::sync::RwLock<AppState>;
+#[tauri::command]
+async fn get_mihomo_status(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<bool, String> {
+    Ok(controller.state() == core_controller::CoreState::Running)
+}
 
 #[tauri::command]
-async fn get_mihomo_status(state: State<'_, AppStateType>) -> Result<bool, String> {
-    let app_state = state.read().await;
-    Ok(app_state.mihomo_running)
+async fn get_core_status(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<core_controller::CoreStatus, String> {
+    Ok(controller.status())
 }
 
 #[tauri::command]
 async fn start_mihomo_service(
-    state: State<'_, AppStateType>,
-    app: tauri::AppHandle,
-    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
 ) -> Result<String, String> {
-    // 防止并发启动 - 获取启动锁
-    let _start_lock = SERVICE_START_LOCK.lock().await;
-    
-    match mihomo::start_mihomo().await {
-        Ok(process_id) => {
-            {
-                let mut app_state = state.write().await;
-                app_state.mihomo_running = true;
-                app_state.mihomo_process = Some(process_id);
-            }
-
-            // 更新 watchdog 跟踪的进程
-            watchdog.set_process(process_id).await;
-
-            events::emit_mihomo_status(
-                &app,
-                events::MihomoStatusEvent {
-                    running: true,
-                    process_id: Some(process_id),
-                    timestamp: events::get_current_timestamp(),
-                },
-            );
-
-            Ok("Mihomo service started successfully".to_string())
-        }
-        Err(e) => Err(format!("Failed to start mihomo: {}", e)),
-    }
+    controller
+        .start()
+        .await
+        .map(|_| "Mihomo service started successfully".to_string())
+        .map_err(|e| format!("Failed to start mihomo: {}", e))
 }
 
 #[tauri::command]
 async fn stop_mihomo_service(
-    state: State<'_, AppStateType>,
-    app: tauri::AppHandle,
-    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
 ) -> Result<String, String> {
-    match mihomo::stop_mihomo().await {
-        Ok(_) => {
-            {
-                let mut app_state = state.write().await;
-                app_state.mihomo_running = false;
-                app_state.mihomo_process = None;
-            }
-
-            // 清除 watchdog 跟踪（会自动禁用自动重启，防止竞态条件）
-            watchdog.clear_process().await;
-
-            // 等待一小段时间确保服务完全停止
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-            events::emit_mihomo_status(
-                &app,
-                events::MihomoStatusEvent {
-                    running: false,
-                    process_id: None,
-                    timestamp: events::get_current_timestamp(),
-                },
-            );
+    controller
+        .stop()
+        .await
+        .map(|_| "Mihomo service stopped successfully".to_string())
+        .map_err(|e| format!("Failed to stop mihomo: {}", e))
+}
 
-            Ok("Mihomo service stopped successfully".to_string())
-        }
-        Err(e) => Err(format!("Failed to stop mihomo: {}", e)),
-    }
+/// 结束所有 mihomo 进程，包括不是由本应用启动的实例
+#[tauri::command]
+async fn kill_all_mihomo_processes(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    controller
+        .kill_all()
+        .await
+        .map(|_| "All mihomo processes killed".to_string())
+        .map_err(|e| format!("Failed to kill mihomo processes: {}", e))
 }
 
 #[tauri::command]
@@ -222,13 +175,31 @@ async fn get_mihomo_config() -> Result<serde_json::Value, String> {
         .map_err(|e| format!("Failed to load config: {}", e))
 }
 
+/// 将已保存的配置应用到运行中的核心，重启核心时同步更新状态
+async fn apply_config_to_core(
+    previous: Option<&serde_json::Value>,
+    controller: &core_controller::CoreController,
+) -> mihomo::ApplyResult {
+    controller
+        .with_restart(mihomo::apply_config(previous), |result| result.process_id)
+        .await
+}
+
 #[tauri::command]
 async fn save_mihomo_config(
     config: serde_json::Value,
     app: tauri::AppHandle,
-) -> Result<String, String> {
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<mihomo::ApplyResult, String> {
+    // 先让核心检查配置，被拒绝的配置不写入 config.yaml
+    mihomo::ensure_core_accepts(&config)
+        .await
+        .map_err(|e| format!("Failed to save config: {}", e))?;
+
+    let previous = config::load_config().await.ok();
+
     match config::save_config(config).await {
-        Ok(_) => {
+        Ok(method) => {
             events::emit_config_change(
                 &app,
                 events::ConfigChangeEvent {
@@ -238,14 +209,16 @@ async fn save_mihomo_config(
                     timestamp: events::get_current_timestamp(),
                 },
             );
-            Ok("Configuration saved successfully".to_string())
+            let mut applied = apply_config_to_core(previous.as_ref(), &controller).await;
+            applied.config_rewritten = method == config_manager::WriteMethod::Rewritten;
+            Ok(applied)
         }
         Err(e) => Err(format!("Failed to save config: {}", e)),
     }
 }
 
 #[tauri::command]
-async fn get_proxies() -> Result<serde_json::Value, String> {
+async fn get_proxies() -> Result<api_client::ProxiesResponse, String> {
     mihomo::get_proxies()
         .await
         .map_err(|e| format!("Failed to get proxies: {}", e))
@@ -255,24 +228,191 @@ async fn get_proxies() -> Result<serde_json::Value, String> {
 async fn switch_proxy(
     group_name: String,
     proxy_name: String,
+    close_connections: Option<bool>,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
     match mihomo::switch_proxy(&group_name, &proxy_name).await {
         Ok(_) => {
+            // 保存选择，核心重启或重新生成配置后恢复
+            selection::remember(&group_name, &proxy_name).await;
+
             events::emit_proxy_change(
                 &app,
                 events::ProxyChangeEvent {
                     group_name: group_name.clone(),
                     proxy_name: proxy_name.clone(),
+                    reason: events::ProxyChangeReason::Manual,
                     timestamp: events::get_current_timestamp(),
                 },
             );
+
+            // 断开该代理组上的现有连接，让会话切换到新节点
+            if close_connections.unwrap_or(false) {
+                match mihomo::close_group_connections(&group_name).await {
+                    Ok(closed) => {
+                        return Ok(format!(
+                            "Proxy switched successfully, {} connections closed",
+                            closed
+                        ))
+                    }
+                    Err(e) => tracing::warn!("Failed to close group connections: {}", e),
+                }
+            }
+
             Ok("Proxy switched successfully".to_string())
         }
         Err(e) => Err(format!("Failed to switch proxy: {}", e)),
     }
 }
 
+/// 修改运行时配置并同步托盘与前端
+async fn apply_runtime_patch(
+    app: &tauri::AppHandle,
+    patch: api_client::RuntimeConfigPatch,
+    persist: bool,
+) -> Result<(), String> {
+    mihomo::patch_runtime_config(&patch, persist)
+        .await
+        .map_err(|e| format!("Failed to update runtime config: {}", e))?;
+
+    if let Some(mode) = patch.mode {
+        update_tray_mode(app, mode);
+    }
+
+    events::emit_mode_change(
+        app,
+        events::ModeChangeEvent {
+            mode: patch.mode,
+            log_level: patch.log_level,
+            allow_lan: patch.allow_lan,
+            persisted: persist,
+            timestamp: events::get_current_timestamp(),
+        },
+    );
+
+    Ok(())
+}
+
+/// 托盘菜单中勾选当前代理模式
+fn update_tray_mode(app: &tauri::AppHandle, mode: api_client::ProxyMode) {
+    let tray = app.tray_handle();
+    for (id, item_mode) in TRAY_MODE_ITEMS {
+        let _ = tray.get_item(id).set_selected(*item_mode == mode);
+    }
+}
+
+const TRAY_MODE_ITEMS: &[(&str, api_client::ProxyMode)] = &[
+    ("mode_rule", api_client::ProxyMode::Rule),
+    ("mode_global", api_client::ProxyMode::Global),
+    ("mode_direct", api_client::ProxyMode::Direct),
+];
+
+#[tauri::command]
+async fn set_runtime_mode(
+    mode: api_client::ProxyMode,
+    persist: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let patch = api_client::RuntimeConfigPatch {
+        mode: Some(mode),
+        ..Default::default()
+    };
+    apply_runtime_patch(&app, patch, persist.unwrap_or(false)).await?;
+    Ok(format!("Mode switched to {}", mode.as_str()))
+}
+
+#[tauri::command]
+async fn set_runtime_log_level(
+    level: core_log::LogLevel,
+    persist: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let patch = api_client::RuntimeConfigPatch {
+        log_level: Some(level),
+        ..Default::default()
+    };
+    apply_runtime_patch(&app, patch, persist.unwrap_or(false)).await?;
+    Ok(format!("Log level set to {}", level.as_str()))
+}
+
+#[tauri::command]
+async fn set_allow_lan(
+    allow: bool,
+    persist: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let patch = api_client::RuntimeConfigPatch {
+        allow_lan: Some(allow),
+        ..Default::default()
+    };
+    apply_runtime_patch(&app, patch, persist.unwrap_or(false)).await?;
+    Ok(format!(
+        "Allow LAN {}",
+        if allow { "enabled" } else { "disabled" }
+    ))
+}
+
+#[tauri::command]
+async fn list_providers() -> Result<mihomo::ProvidersOverview, String> {
+    mihomo::list_providers()
+        .await
+        .map_err(|e| format!("Failed to list providers: {}", e))
+}
+
+#[tauri::command]
+async fn update_provider(
+    name: String,
+    kind: Option<api_client::ProviderKind>,
+) -> Result<String, String> {
+    mihomo::update_provider(&name, kind)
+        .await
+        .map_err(|e| format!("Failed to update provider: {}", e))?;
+    Ok(format!("Provider {} updated", name))
+}
+
+#[tauri::command]
+async fn healthcheck_provider(name: String) -> Result<api_client::ProxyProviderInfo, String> {
+    mihomo::healthcheck_provider(&name)
+        .await
+        .map_err(|e| format!("Failed to health check provider: {}", e))
+}
+
+#[tauri::command]
+async fn list_connections() -> Result<api_client::ConnectionsSnapshot, String> {
+    mihomo::list_connections()
+        .await
+        .map_err(|e| format!("Failed to list connections: {}", e))
+}
+
+#[tauri::command]
+async fn close_connection(id: String) -> Result<String, String> {
+    mihomo::close_connection(&id)
+        .await
+        .map_err(|e| format!("Failed to close connection: {}", e))
+        .map(|_| "Connection closed".to_string())
+}
+
+#[tauri::command]
+async fn close_all_connections() -> Result<String, String> {
+    mihomo::close_all_connections()
+        .await
+        .map_err(|e| format!("Failed to close connections: {}", e))
+        .map(|_| "All connections closed".to_string())
+}
+
+#[tauri::command]
+async fn start_connections_stream(
+    interval_ms: Option<u64>,
+    app: tauri::AppHandle,
+) -> Result<u64, String> {
+    Ok(connections::start_stream(app, interval_ms))
+}
+
+#[tauri::command]
+async fn stop_connections_stream() -> Result<bool, String> {
+    Ok(connections::stop_stream())
+}
+
 #[tauri::command]
 async fn add_subscription(
     name: String,
@@ -294,11 +434,21 @@ async fn get_subscriptions() -> Result<Vec<subscription::Subscription>, String>
 }
 
 #[tauri::command]
-async fn update_subscription(id: String) -> Result<String, String> {
+async fn update_subscription(
+    id: String,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    let previous = config::load_config().await.ok();
+
     subscription::update_subscription(&id)
         .await
-        .map_err(|e| format!("Failed to update subscription: {}", e))
-        .map(|_| "Subscription updated successfully".to_string())
+        .map_err(|e| format!("Failed to update subscription: {}", e))?;
+
+    let applied = apply_config_to_core(previous.as_ref(), &controller).await;
+    Ok(format!(
+        "Subscription updated successfully. {}",
+        applied.message
+    ))
 }
 
 #[tauri::command]
@@ -312,26 +462,47 @@ async fn delete_subscription(id: String) -> Result<String, String> {
 #[tauri::command]
 async fn generate_config_from_subscriptions(
     subscription_ids: Vec<String>,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
 ) -> Result<String, String> {
+    let previous = config::load_config().await.ok();
+
     subscription::generate_config_from_subscriptions(subscription_ids)
         .await
-        .map_err(|e| format!("Failed to generate config: {}", e))
-        .map(|_| "Configuration generated successfully".to_string())
+        .map_err(|e| format!("Failed to generate config: {}", e))?;
+
+    let applied = apply_config_to_core(previous.as_ref(), &controller).await;
+    Ok(format!(
+        "Configuration generated successfully. {}",
+        applied.message
+    ))
 }
 
 #[tauri::command]
-async fn enable_tun_mode(enable: bool) -> Result<String, String> {
-    config::set_tun_mode(enable)
+async fn enable_tun_mode(
+    enable: bool,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    let previous = config::load_config().await.ok();
+
+    let method = config::set_tun_mode(enable)
         .await
-        .map_err(|e| format!("Failed to set TUN mode: {}", e))
-        .map(|_| {
-            if enable {
-                "TUN mode enabled"
-            } else {
-                "TUN mode disabled"
-            }
-            .to_string()
-        })
+        .map_err(|e| format!("Failed to set TUN mode: {}", e))?;
+
+    let applied = apply_config_to_core(previous.as_ref(), &controller).await;
+    Ok(format!(
+        "{}. {}{}",
+        if enable {
+            "TUN mode enabled"
+        } else {
+            "TUN mode disabled"
+        },
+        applied.message,
+        if method == config_manager::WriteMethod::Rewritten {
+            " (config file was rewritten; comments were not preserved)"
+        } else {
+            ""
+        }
+    ))
 }
 
 #[tauri::command]
@@ -353,6 +524,61 @@ async fn get_auto_restart(
     Ok(watchdog.get_auto_restart().await)
 }
 
+#[tauri::command]
+async fn get_watchdog_status(
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<watchdog::WatchdogStatus, String> {
+    Ok(watchdog.status().await)
+}
+
+#[tauri::command]
+async fn pause_watchdog(
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<String, String> {
+    watchdog.pause().await;
+    Ok("Watchdog paused".to_string())
+}
+
+#[tauri::command]
+async fn resume_watchdog(
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<String, String> {
+    watchdog.resume().await;
+    Ok("Watchdog resumed".to_string())
+}
+
+#[tauri::command]
+async fn rearm_auto_restart(
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<String, String> {
+    watchdog.rearm_auto_restart().await;
+    Ok("Auto-restart re-armed".to_string())
+}
+
+#[tauri::command]
+async fn get_crash_history() -> Result<Vec<crash_history::CrashRecord>, String> {
+    Ok(crash_history::history().await)
+}
+
+#[tauri::command]
+async fn get_watchdog_policy(
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<watchdog::WatchdogPolicy, String> {
+    Ok(watchdog.get_policy().await)
+}
+
+#[tauri::command]
+async fn set_watchdog_policy(
+    policy: watchdog::WatchdogPolicy,
+    watchdog: State<'_, std::sync::Arc<watchdog::ProcessWatchdog>>,
+) -> Result<String, String> {
+    watchdog
+        .set_policy(policy)
+        .await
+        .map_err(|e| format!("Failed to set watchdog policy: {}", e))?;
+    Ok("Watchdog policy updated".to_string())
+}
+
 #[tauri::command]
 async fn test_group_delay(group_name: String) -> Result<String, String> {
     mihomo::test_group_delay(&group_name)
@@ -390,14 +616,60 @@ async fn get_current_ip() -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 async fn test_all_proxies(
-    _test_url: Option<String>,
-    _timeout: Option<u32>,
-) -> Result<serde_json::Value, String> {
-    // 使用 test_all_groups_delay 来测速，这样会更新 Mihomo 内部状态
-    // test_url 和 timeout 参数暂时忽略，使用默认值
-    mihomo::test_all_groups_delay()
+    test_url: Option<String>,
+    timeout: Option<u32>,
+    concurrency: Option<usize>,
+    expected_status: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<mihomo::DelayTestSummary, String> {
+    let defaults = mihomo::DelayTestOptions::default();
+    let options = mihomo::DelayTestOptions {
+        test_url: test_url
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or(defaults.test_url),
+        timeout: timeout.unwrap_or(defaults.timeout),
+        concurrency: concurrency.unwrap_or(defaults.concurrency).clamp(1, 64),
+        expected_status: expected_status.filter(|s| !s.trim().is_empty()),
+    };
+
+    // 通过核心测速，这样会更新 Mihomo 内部的延迟记录
+    mihomo::test_all_groups_delay(&options, |progress| {
+        events::emit_delay_progress(&app, progress)
+    })
+    .await
+    .map_err(|e| format!("Failed to test all proxies: {}", e))
+}
+
+#[tauri::command]
+async fn get_node_stats(
+    name: String,
+    subscription: Option<String>,
+    window_hours: Option<u32>,
+) -> Result<latency::NodeStats, String> {
+    latency::node_stats(
+        &name,
+        subscription.as_deref(),
+        window_hours.unwrap_or(latency::DEFAULT_WINDOW_HOURS),
+    )
+    .await
+    .map_err(|e| format!("Failed to get node stats: {}", e))
+}
+
+#[tauri::command]
+async fn get_core_logs(
+    level: Option<core_log::LogLevel>,
+    limit: Option<usize>,
+) -> Result<Vec<core_log::CoreLogEntry>, String> {
+    Ok(core_log::recent(level, limit.unwrap_or(200)))
+}
+
+#[tauri::command]
+async fn test_config_with_core(
+    config: serde_json::Value,
+) -> Result<mihomo::CoreTestResult, String> {
+    mihomo::test_config_with_core(&config)
         .await
-        .map_err(|e| format!("Failed to test all proxies: {}", e))
+        .map_err(|e| format!("Failed to test config: {}", e))
 }
 
 #[tauri::command]
@@ -415,8 +687,19 @@ async fn list_config_backups() -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-async fn restore_config_backup(backup_filename: String) -> Result<backup::RestoreResult, String> {
-    backup::restore_config(&backup_filename)
+async fn restore_config_backup(
+    backup_filename: String,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<backup::RestoreResult, String> {
+    // 恢复过程中如果重启了核心，同步更新状态
+    controller
+        .with_restart(backup::restore_config(&backup_filename), |result| {
+            result
+                .as_ref()
+                .ok()
+                .and_then(|r| r.apply.as_ref())
+                .and_then(|a| a.process_id)
+        })
         .await
         .map_err(|e| format!("Failed to restore backup: {}", e))
 }
@@ -438,38 +721,94 @@ async fn rename_config_backup(old_filename: String, new_label: String) -> Result
 
 #[tauri::command]
 async fn check_mihomo_binary() -> Result<String, String> {
-    // 根据平台检查不同的可执行文件
-    #[cfg(target_os = "windows")]
-    let mihomo_paths = vec![
-        "mihomo.exe",
-        "clash-meta.exe",
-        "./mihomo.exe",
-        "./clash-meta.exe",
-    ];
+    match core_locator::detect().await {
+        Ok(info) => Ok(format!(
+            "找到 mihomo: {} - {}",
+            info.path.display(),
+            info.raw
+        )),
+        Err(e) => Err(format!(
+            "{}。请从 https://github.com/MetaCubeX/mihomo/releases 下载并安装。",
+            e
+        )),
+    }
+}
 
-    #[cfg(not(target_os = "windows"))]
-    let mihomo_paths = vec![
-        "mihomo",
-        "clash-meta",
-        "./mihomo",
-        "./clash-meta",
-        "/usr/bin/mihomo",
-        "/usr/local/bin/mihomo",
-    ];
+#[tauri::command]
+async fn get_core_info() -> Result<core_locator::CoreInfo, String> {
+    core_locator::detect()
+        .await
+        .map_err(|e| format!("Failed to detect mihomo core: {}", e))
+}
 
-    for path in mihomo_paths {
-        if let Ok(output) = std::process::Command::new(path).arg("--version").output() {
-            if output.status.success() {
-                let version = String::from_utf8_lossy(&output.stdout);
-                return Ok(format!("找到 mihomo: {} - {}", path, version.trim()));
-            }
-        }
-    }
+/// 指定核心路径，传入 None 恢复自动查找
+#[tauri::command]
+async fn set_core_path(path: Option<String>) -> Result<core_locator::CoreInfo, String> {
+    core_locator::set_user_override(path)
+        .await
+        .map_err(|e| format!("Failed to set core path: {}", e))
+}
 
-    Err(
-        "未找到 mihomo 二进制文件。请从 https://github.com/MetaCubeX/mihomo/releases 下载并安装。"
-            .to_string(),
-    )
+#[tauri::command]
+async fn list_cores() -> Result<Vec<core_manager::InstalledCore>, String> {
+    core_manager::list_installed()
+        .await
+        .map_err(|e| format!("Failed to list cores: {}", e))
+}
+
+/// 下载并安装核心，`url` 为空时使用配置的下载地址
+#[tauri::command]
+async fn install_core(
+    version: String,
+    sha256: String,
+    url: Option<String>,
+) -> Result<core_manager::InstalledCore, String> {
+    core_manager::install_from_url(&version, url.as_deref(), &sha256)
+        .await
+        .map_err(|e| format!("Failed to install core: {}", e))
+}
+
+#[tauri::command]
+async fn install_core_from_file(
+    version: String,
+    path: String,
+    sha256: String,
+) -> Result<core_manager::InstalledCore, String> {
+    core_manager::install_from_file(&version, std::path::Path::new(&path), &sha256)
+        .await
+        .map_err(|e| format!("Failed to install core: {}", e))
+}
+
+#[tauri::command]
+async fn switch_core(
+    version: String,
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<core_manager::SwitchResult, String> {
+    controller
+        .with_restart(core_manager::switch_to(&version), |result| {
+            result.as_ref().ok().and_then(|r| r.process_id)
+        })
+        .await
+        .map_err(|e| format!("Failed to switch core: {}", e))
+}
+
+#[tauri::command]
+async fn rollback_core(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<core_manager::SwitchResult, String> {
+    controller
+        .with_restart(core_manager::rollback(), |result| {
+            result.as_ref().ok().and_then(|r| r.process_id)
+        })
+        .await
+        .map_err(|e| format!("Failed to roll back core: {}", e))
+}
+
+#[tauri::command]
+async fn remove_core(version: String) -> Result<(), String> {
+    core_manager::remove(&version)
+        .await
+        .map_err(|e| format!("Failed to remove core: {}", e))
 }
 
 #[tauri::command]
@@ -568,45 +907,10 @@ async fn restart_as_admin() -> Result<String, String> {
 
 #[tauri::command]
 async fn get_bundled_mihomo_path() -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    let mihomo_binary = "mihomo.exe";
-    #[cfg(not(target_os = "windows"))]
-    let mihomo_binary = "mihomo";
-
-    // 系统路径列表
-    #[cfg(target_os = "windows")]
-    let system_paths: Vec<&str> = vec![];
-    #[cfg(not(target_os = "windows"))]
-    let system_paths = vec![
-        "/usr/local/bin/mihomo",
-        "/usr/bin/mihomo",
-        "/opt/mihomo/mihomo",
-    ];
-
-    // 首先检查系统路径
-    for path in &system_paths {
-        if std::path::Path::new(path).exists() {
-            return Ok(path.to_string());
-        }
-    }
-
-    // 如果系统路径找不到，检查应用目录
-    let app_dir = std::env::current_exe()
-        .map_err(|e| format!("获取应用目录失败: {}", e))?
-        .parent()
-        .ok_or("无法获取应用目录")?
-        .to_path_buf();
-
-    let mihomo_path = app_dir.join(mihomo_binary);
-
-    if mihomo_path.exists() {
-        Ok(mihomo_path.to_string_lossy().to_string())
-    } else {
-        Err(format!(
-            "未找到 {} 文件。请确保 mihomo 已安装在系统路径或应用目录中",
-            mihomo_binary
-        ))
-    }
+    core_locator::locate()
+        .await
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -617,7 +921,7 @@ async fn install_mihomo_service() -> Result<String, String> {
 
         let app_dir = resolve_app_dir()?;
         let winsw_source = resolve_winsw_source(&app_dir)?;
-        let mihomo_path = resolve_mihomo_path(&app_dir)?;
+        let mihomo_path = core_locator::locate().await.map_err(|e| e.to_string())?;
 
         // 创建配置目录
         let config_dir = dirs::config_dir().ok_or("无法获取配置目录")?.join("mihomo");
@@ -660,7 +964,6 @@ async fn install_mihomo_service() -> Result<String, String> {
     #[cfg(not(target_os = "windows"))]
     {
         use std::fs;
-        use std::path::Path;
         use std::process::Command;
 
         // 创建配置目录
@@ -677,34 +980,8 @@ async fn install_mihomo_service() -> Result<String, String> {
                 .map_err(|e| format!("创建默认配置失败: {}", e))?;
         }
 
-        // 检查 mihomo 二进制文件是否存在
-        let mihomo_path = "/usr/local/bin/mihomo";
-        if !Path::new(mihomo_path).exists() {
-            // 尝试从系统PATH中找到mihomo
-            match Command::new("which").arg("mihomo").output() {
-                Ok(output) if output.status.success() => {
-                    // mihomo exists in PATH
-                }
-                _ => {
-                    return Err("未找到 mihomo 二进制文件，请先安装 mihomo 到 /usr/local/bin/mihomo 或系统PATH中".to_string());
-                }
-            }
-        }
-
         // 确定 mihomo 二进制文件路径
-        let mihomo_binary = if Path::new("/usr/local/bin/mihomo").exists() {
-            "/usr/local/bin/mihomo".to_string()
-        } else if Path::new("/usr/bin/mihomo").exists() {
-            "/usr/bin/mihomo".to_string()
-        } else {
-            // 尝试从PATH中找到
-            match Command::new("which").arg("mihomo").output() {
-                Ok(output) if output.status.success() => {
-                    String::from_utf8_lossy(&output.stdout).trim().to_string()
-                }
-                _ => "/usr/local/bin/mihomo".to_string(), // 默认路径
-            }
-        };
+        let mihomo_binary = core_locator::locate().await.map_err(|e| e.to_string())?;
 
         // 创建 systemd 服务文件内容
         let service_content = format!(
@@ -723,7 +1000,7 @@ Group=root
 [Install]
 WantedBy=multi-user.target
 "#,
-            mihomo_binary,
+            mihomo_binary.display(),
             config_path
                 .parent()
                 .ok_or_else(|| "Failed to get config directory".to_string())?
@@ -763,14 +1040,21 @@ WantedBy=multi-user.target
 }
 
 #[tauri::command]
-async fn start_mihomo_service_cmd() -> Result<String, String> {
+async fn start_mihomo_service_cmd(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    controller.start_service(start_system_service()).await
+}
+
+/// 启动系统服务（systemd / WinSW）
+async fn start_system_service() -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
 
         let app_dir = resolve_app_dir()?;
         let winsw_source = resolve_winsw_source(&app_dir)?;
-        let mihomo_path = resolve_mihomo_path(&app_dir)?;
+        let mihomo_path = core_locator::locate().await.map_err(|e| e.to_string())?;
         let config_path = dirs::config_dir()
             .ok_or("无法获取配置目录")?
             .join("mihomo")
@@ -835,14 +1119,21 @@ async fn start_mihomo_service_cmd() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn stop_mihomo_service_cmd() -> Result<String, String> {
+async fn stop_mihomo_service_cmd(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    controller.stop_service(stop_system_service()).await
+}
+
+/// 停止系统服务
+async fn stop_system_service() -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
 
         let app_dir = resolve_app_dir()?;
         let winsw_source = resolve_winsw_source(&app_dir)?;
-        let mihomo_path = resolve_mihomo_path(&app_dir)?;
+        let mihomo_path = core_locator::locate().await.map_err(|e| e.to_string())?;
         let config_path = dirs::config_dir()
             .ok_or("无法获取配置目录")?
             .join("mihomo")
@@ -886,14 +1177,21 @@ async fn stop_mihomo_service_cmd() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn restart_mihomo_service_cmd() -> Result<String, String> {
+async fn restart_mihomo_service_cmd(
+    controller: State<'_, std::sync::Arc<core_controller::CoreController>>,
+) -> Result<String, String> {
+    controller.restart_service(restart_system_service()).await
+}
+
+/// 重启系统服务
+async fn restart_system_service() -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
 
         let app_dir = resolve_app_dir()?;
         let winsw_source = resolve_winsw_source(&app_dir)?;
-        let mihomo_path = resolve_mihomo_path(&app_dir)?;
+        let mihomo_path = core_locator::locate().await.map_err(|e| e.to_string())?;
         let config_path = dirs::config_dir()
             .ok_or("无法获取配置目录")?
             .join("mihomo")
@@ -945,7 +1243,7 @@ async fn uninstall_mihomo_service() -> Result<String, String> {
 
         let app_dir = resolve_app_dir()?;
         let winsw_source = resolve_winsw_source(&app_dir)?;
-        let mihomo_path = resolve_mihomo_path(&app_dir)?;
+        let mihomo_path = core_locator::locate().await.map_err(|e| e.to_string())?;
         let config_path = dirs::config_dir()
             .ok_or("无法获取配置目录")?
             .join("mihomo")
@@ -1269,19 +1567,24 @@ fn main() {
     let stop_service = CustomMenuItem::new("stop".to_string(), "停止服务");
     let quit = CustomMenuItem::new("quit".to_string(), "退出");
 
+    let mode_menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("mode_rule".to_string(), "规则模式"))
+        .add_item(CustomMenuItem::new("mode_global".to_string(), "全局模式"))
+        .add_item(CustomMenuItem::new("mode_direct".to_string(), "直连模式"));
+
     let tray_menu = SystemTrayMenu::new()
         .add_item(show)
         .add_item(hide)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(start_service)
         .add_item(stop_service)
+        .add_submenu(SystemTraySubmenu::new("代理模式", mode_menu))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit);
 
     let system_tray = SystemTray::new().with_menu(tray_menu);
 
     tauri::Builder::default()
-        .manage(AppStateType::new(AppState::default()))
         .system_tray(system_tray)
         .on_system_tray_event(|app, event| match event {
             SystemTrayEvent::LeftClick {
@@ -1309,20 +1612,44 @@ fn main() {
                     }
                     "start" => {
                         // 启动服务
+                        let controller = app
+                            .state::<std::sync::Arc<core_controller::CoreController>>()
+                            .inner()
+                            .clone();
                         tauri::async_runtime::spawn(async move {
-                            let _ = mihomo::start_mihomo().await;
+                            let _ = controller.start().await;
                         });
                     }
                     "stop" => {
                         // 停止服务
+                        let controller = app
+                            .state::<std::sync::Arc<core_controller::CoreController>>()
+                            .inner()
+                            .clone();
                         tauri::async_runtime::spawn(async move {
-                            let _ = mihomo::stop_mihomo().await;
+                            let _ = controller.stop().await;
                         });
                     }
                     "quit" => {
                         std::process::exit(0);
                     }
-                    _ => {}
+                    id => {
+                        // 切换代理模式，并写入配置以便重启后保持
+                        if let Some((_, mode)) =
+                            TRAY_MODE_ITEMS.iter().find(|(item, _)| *item == id)
+                        {
+                            let app = app.clone();
+                            let patch = api_client::RuntimeConfigPatch {
+                                mode: Some(*mode),
+                                ..Default::default()
+                            };
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = apply_runtime_patch(&app, patch, true).await {
+                                    tracing::warn!("{}", e);
+                                }
+                            });
+                        }
+                    }
                 }
             }
             _ => {}
@@ -1336,12 +1663,25 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             get_mihomo_status,
+            get_core_status,
             start_mihomo_service,
             stop_mihomo_service,
+            kill_all_mihomo_processes,
             get_mihomo_config,
             save_mihomo_config,
             get_proxies,
             switch_proxy,
+            list_connections,
+            close_connection,
+            close_all_connections,
+            start_connections_stream,
+            stop_connections_stream,
+            set_runtime_mode,
+            set_runtime_log_level,
+            set_allow_lan,
+            list_providers,
+            update_provider,
+            healthcheck_provider,
             add_subscription,
             get_subscriptions,
             update_subscription,
@@ -1350,15 +1690,33 @@ fn main() {
             enable_tun_mode,
             set_auto_restart,
             get_auto_restart,
+            get_watchdog_status,
+            pause_watchdog,
+            resume_watchdog,
+            rearm_auto_restart,
+            get_crash_history,
+            get_watchdog_policy,
+            set_watchdog_policy,
             test_group_delay,
             test_all_proxies,
+            get_node_stats,
+            get_core_logs,
             validate_config,
+            test_config_with_core,
             list_config_backups,
             restore_config_backup,
             delete_config_backup,
             rename_config_backup,
             get_current_ip,
             check_mihomo_binary,
+            get_core_info,
+            set_core_path,
+            list_cores,
+            install_core,
+            install_core_from_file,
+            switch_core,
+            rollback_core,
+            remove_core,
             check_admin_privileges,
             restart_as_admin,
             get_bundled_mihomo_path,
@@ -1386,8 +1744,25 @@ fn main() {
                 config_manager::init_config_manager(config_path).await;
             });
 
+            // 初始化核心日志（环形缓冲区 + 日志文件）
+            core_log::init(app.handle());
+
+            // 核心状态机，所有启停操作都经过它
+            let controller =
+                std::sync::Arc::new(core_controller::CoreController::new(app.handle()));
+            app.manage(controller.clone());
+
             // 初始化 watchdog
-            let watchdog = std::sync::Arc::new(watchdog::ProcessWatchdog::new(app.handle()));
+            let policy =
+                tauri::async_runtime::block_on(watchdog::load_policy()).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load watchdog policy, using defaults: {}", e);
+                    watchdog::WatchdogPolicy::default()
+                });
+            let watchdog = std::sync::Arc::new(watchdog::ProcessWatchdog::new(
+                app.handle(),
+                controller.clone(),
+                policy,
+            ));
             app.manage(watchdog.clone());
 
             // 启动 watchdog 监控
@@ -1396,6 +1771,22 @@ fn main() {
                 watchdog_clone.start_monitoring().await;
             });
 
+            // 核心可用时推送实时流量
+            traffic::spawn_traffic_stream(app.handle(), controller.subscribe_running());
+            core_log::spawn_log_stream(controller.subscribe_running());
+            selection::spawn_restore_on_start(controller.subscribe_running());
+
+            // 托盘勾选配置中的代理模式
+            let configured_mode = tauri::async_runtime::block_on(config::load_config())
+                .ok()
+                .and_then(|c| c.get("mode").and_then(|v| v.as_str()).map(str::to_string));
+            let configured_mode = match configured_mode.as_deref() {
+                Some("global") => api_client::ProxyMode::Global,
+                Some("direct") => api_client::ProxyMode::Direct,
+                _ => api_client::ProxyMode::Rule,
+            };
+            update_tray_mode(&app.handle(), configured_mode);
+
             // 检查是否启用静默启动
             let config_dir = dirs::config_dir();
             let mut silent_start = false;
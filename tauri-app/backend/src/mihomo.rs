@@ -1,35 +1,107 @@
+use crate::api_client::{
+    ConnectionsSnapshot, ControllerSettings, DelayQuery, MihomoApiClient, ProviderKind,
+    ProxiesResponse, ProxyProviderInfo, RuleProviderInfo, RuntimeConfigPatch, VersionInfo,
+};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::{Command, Stdio};
-use tokio::io::AsyncReadExt;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
 use tokio::process::Command as TokioCommand;
 use tracing::{info, warn};
 
-#[allow(dead_code)]
+/// 批量测速结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProxyGroup {
-    pub name: String,
-    pub r#type: String,
-    pub now: Option<String>,
-    pub all: Vec<String>,
-    pub history: Vec<ProxyHistory>,
+pub struct DelayTestSummary {
+    pub total: usize,
+    pub tested: usize,
+    pub success: usize,
+    pub results: HashMap<String, Option<u32>>,
 }
 
-#[allow(dead_code)]
+/// 默认测速地址
+pub const DEFAULT_TEST_URL: &str = "http://www.gstatic.com/generate_204";
+
+/// 延迟测试参数
+#[derive(Debug, Clone)]
+pub struct DelayTestOptions {
+    pub test_url: String,
+    /// 单个节点的超时时间（毫秒）
+    pub timeout: u32,
+    /// 同时测试的节点数
+    pub concurrency: usize,
+    /// 期望的 HTTP 状态码，如 "204" 或 "200-299"
+    pub expected_status: Option<String>,
+}
+
+impl Default for DelayTestOptions {
+    fn default() -> Self {
+        Self {
+            test_url: DEFAULT_TEST_URL.to_string(),
+            timeout: 5000,
+            concurrency: 10,
+            expected_status: None,
+        }
+    }
+}
+
+impl DelayTestOptions {
+    fn query(&self) -> DelayQuery {
+        DelayQuery {
+            expected: self.expected_status.clone(),
+            ..DelayQuery::new(&self.test_url, self.timeout)
+        }
+    }
+}
+
+/// 代理与规则 provider 列表
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProxyHistory {
-    pub name: String,
-    pub delay: u32,
-    pub time: String,
+pub struct ProvidersOverview {
+    pub proxy_providers: Vec<ProxyProviderInfo>,
+    pub rule_providers: Vec<RuleProviderInfo>,
+}
+
+/// 启动失败时错误信息中附带的输出行数
+const STARTUP_ERROR_CONTEXT_LINES: usize = 20;
+
+/// `mihomo -t` 的最长执行时间，首次运行可能需要下载 GeoIP 等数据
+const CORE_TEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 修改后无法热重载、需要重启核心才能生效的配置项
+const RESTART_REQUIRED_KEYS: &[&str] = &[
+    "external-controller",
+    "external-controller-tls",
+    "external-controller-unix",
+    "secret",
+];
+
+/// 配置应用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyMethod {
+    /// 通过 PUT /configs 热重载
+    HotReload,
+    /// 存在无法热重载的字段，已重启核心
+    Restart,
+    /// 核心未运行，下次启动时生效
+    NotRunning,
+    /// 配置未通过验证，未应用
+    Skipped,
 }
 
-#[allow(dead_code)]
+/// 配置应用结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProxyNode {
-    pub name: String,
-    pub r#type: String,
-    pub delay: Option<u32>,
-    pub alive: bool,
+pub struct ApplyResult {
+    pub method: ApplyMethod,
+    pub success: bool,
+    pub message: String,
+    /// 触发重启的字段
+    pub restart_keys: Vec<String>,
+    /// 重启后新进程的 PID
+    pub process_id: Option<u32>,
+    /// 保存时无法局部修改，config.yaml 被整体重写，注释和键顺序已丢失
+    #[serde(default)]
+    pub config_rewritten: bool,
 }
 
 pub async fn start_mihomo() -> Result<u32> {
@@ -51,64 +123,16 @@ pub async fn start_mihomo() -> Result<u32> {
     if is_mihomo_running().await {
         info!("Detected existing mihomo process, stopping it first...");
         let _ = stop_mihomo().await; // 忽略错误，继续启动
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     }
 
-    // 查找 mihomo 可执行文件
-    // 优先使用系统路径，然后才查找应用目录
-
-    #[cfg(target_os = "windows")]
-    let mihomo_binary = "mihomo.exe";
-    #[cfg(not(target_os = "windows"))]
-    let mihomo_binary = "mihomo";
-
-    // 系统路径列表
-    #[cfg(target_os = "windows")]
-    let system_paths: Vec<&str> = vec![];
-    #[cfg(not(target_os = "windows"))]
-    let system_paths = vec![
-        "/usr/local/bin/mihomo",
-        "/usr/bin/mihomo",
-        "/opt/mihomo/mihomo",
-    ];
-
-    // 首先检查系统路径
-    let mut mihomo_path = None;
-    for path in &system_paths {
-        if std::path::Path::new(path).exists() {
-            mihomo_path = Some(std::path::PathBuf::from(path));
-            break;
-        }
-    }
-
-    // 如果系统路径找不到，检查应用目录
-    if mihomo_path.is_none() {
-        let app_dir = std::env::current_exe()
-            .context("获取应用目录失败")?
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("无法获取应用目录"))?
-            .to_path_buf();
-
-        // 检查多个可能的位置
-        let possible_paths = vec![
-            app_dir.join(mihomo_binary),                   // 应用目录根目录
-            app_dir.join("resources").join(mihomo_binary), // resources 子目录（Tauri 打包位置）
-        ];
-
-        for path in possible_paths {
-            if path.exists() {
-                mihomo_path = Some(path);
-                break;
-            }
-        }
+    // API 无响应但进程仍存活的核心（例如卡死）同样需要结束，否则会被遗留
+    match crate::process_manager::terminate(stop_timeout()).await {
+        Ok(true) => info!("Terminated previously started mihomo before starting a new one"),
+        Ok(false) => {}
+        Err(e) => warn!("Failed to terminate previous mihomo: {}", e),
     }
 
-    let mihomo_path = mihomo_path.ok_or_else(|| {
-        anyhow::anyhow!(
-            "未找到 {} 文件。请确保 mihomo 已安装在系统路径或应用目录中",
-            mihomo_binary
-        )
-    })?;
+    let mihomo_path = crate::core_locator::locate().await?;
 
     // 在 Windows 上创建隐藏控制台窗口的命令
     #[cfg(target_os = "windows")]
@@ -138,44 +162,50 @@ pub async fn start_mihomo() -> Result<u32> {
     };
     
     match cmd.spawn() {
-        Ok(mut cmd) => {
-            // Give it a moment to start
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-
-            // Check if the process is still running
-            match cmd.try_wait() {
-                Ok(Some(status)) => {
-                    // Process exited, capture error output
-                    let stderr = cmd.stderr.take();
-                    if let Some(mut stderr) = stderr {
-                        let mut error_output = String::new();
-                        if (stderr.read_to_string(&mut error_output).await).is_ok() {
-                            Err(anyhow::anyhow!(
-                                "Mihomo process exited with status: {} - {}",
-                                status,
-                                error_output
-                            ))
-                        } else {
-                            Err(anyhow::anyhow!(
-                                "Mihomo process exited with status: {}",
-                                status
-                            ))
-                        }
-                    } else {
+        Ok(mut child) => {
+            // 持续读取 stdout/stderr，防止管道写满后核心阻塞
+            let output = crate::process_output::OutputCapture::attach(&mut child);
+            let pid = child.id().unwrap_or(0);
+            let spawned_at = tokio::time::Instant::now();
+            let failed_start = |status| crate::process_manager::ExitInfo {
+                pid,
+                status,
+                uptime: spawned_at.elapsed(),
+                exe: mihomo_path.clone(),
+            };
+
+            // 控制器响应后才算启动成功；进程提前退出则立即报告
+            tokio::select! {
+                ready = wait_until_ready() => match ready {
+                    Ok(version) => {
+                        info!("Mihomo {} is ready (PID: {})", version.version, pid);
+                        crate::process_manager::track(child, &mihomo_path, &config_path).await;
+                        Ok(pid)
+                    }
+                    Err(e) => {
+                        let _ = child.start_kill();
+                        let status = child.wait().await.ok();
+                        crate::process_manager::record_failed_start(failed_start(status)).await;
+                        output.drain().await;
                         Err(anyhow::anyhow!(
-                            "Mihomo process exited with status: {}",
-                            status
+                            "Mihomo did not become ready: {:#}\n{}",
+                            e,
+                            crate::process_output::format_recent(STARTUP_ERROR_CONTEXT_LINES)
                         ))
                     }
-                }
-                Ok(None) => {
-                    let pid = cmd.id().unwrap_or(0);
-                    Ok(pid)
-                }
-                Err(e) => {
+                },
+                status = child.wait() => {
+                    let exit_status = status.as_ref().ok().copied();
+                    crate::process_manager::record_failed_start(failed_start(exit_status)).await;
+                    output.drain().await;
+                    let status = match status {
+                        Ok(status) => status.to_string(),
+                        Err(e) => e.to_string(),
+                    };
                     Err(anyhow::anyhow!(
-                        "Failed to check mihomo process status: {}",
-                        e
+                        "Mihomo process exited with status: {}\n{}",
+                        status,
+                        crate::process_output::format_recent(STARTUP_ERROR_CONTEXT_LINES)
                     ))
                 }
             }
@@ -190,6 +220,126 @@ pub async fn start_mihomo() -> Result<u32> {
     }
 }
 
+/// `mihomo -t` 配置检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreTestResult {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub output: String,
+}
+
+/// 将配置写入临时文件并用核心检查，不会修改 config.yaml
+pub async fn test_config_with_core(config: &serde_json::Value) -> Result<CoreTestResult> {
+    let yaml_value = crate::config::json_to_yaml(config);
+    let yaml_content = serde_yaml::to_string(&yaml_value).context("Failed to serialize YAML")?;
+
+    // 临时文件放在配置目录中，使配置里的相对路径按相同方式解析
+    let config_dir = crate::config::get_mihomo_config_dir()?;
+    let temp_path = config_dir.join(format!(".preflight-{}.yaml", uuid::Uuid::new_v4()));
+    tokio::fs::write(&temp_path, yaml_content)
+        .await
+        .context("Failed to write temp config")?;
+
+    let result = test_config_file_with_core(&temp_path).await;
+    let _ = tokio::fs::remove_file(&temp_path).await;
+    result
+}
+
+/// 用 `mihomo -t -d <dir> -f <file>` 检查配置文件
+pub async fn test_config_file_with_core(path: &std::path::Path) -> Result<CoreTestResult> {
+    let mihomo_path = crate::core_locator::locate().await?;
+    let config_dir = crate::config::get_mihomo_config_dir()?;
+
+    let mut command = TokioCommand::new(&mihomo_path);
+    command
+        .arg("-t")
+        .arg("-d")
+        .arg(&config_dir)
+        .arg("-f")
+        .arg(path)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    // 超时视为未通过：未经检查的配置不能替换 config.yaml
+    let output = match tokio::time::timeout(CORE_TEST_TIMEOUT, command.output()).await {
+        Ok(output) => output.context("Failed to run mihomo config test")?,
+        Err(_) => {
+            let message = format!(
+                "Config test timed out after {}s",
+                CORE_TEST_TIMEOUT.as_secs()
+            );
+            warn!("{}", message);
+            return Ok(CoreTestResult {
+                valid: false,
+                errors: vec![message],
+                output: String::new(),
+            });
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let combined = format!("{}{}", stdout, stderr).trim().to_string();
+
+    let valid = output.status.success();
+    Ok(CoreTestResult {
+        valid,
+        errors: core_test_errors(&combined, valid),
+        output: combined,
+    })
+}
+
+/// 从 `mihomo -t` 的输出中提取错误信息
+fn core_test_errors(output: &str, success: bool) -> Vec<String> {
+    let errors: Vec<String> = output
+        .lines()
+        .filter(|line| line.contains("level=error") || line.contains("level=fatal"))
+        .map(|line| crate::process_output::parse_log_line(line).1)
+        .collect();
+
+    if success || !errors.is_empty() {
+        return errors;
+    }
+
+    // 没有结构化的错误日志时保留原始输出
+    output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.ends_with("test failed"))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// 核心拒绝配置或检查超时时返回错误；无法执行检查（如找不到核心）时只记录警告
+pub async fn ensure_core_accepts(config: &serde_json::Value) -> Result<()> {
+    check_core_test_result(test_config_with_core(config).await)
+}
+
+/// 同 [`ensure_core_accepts`]，检查的是已有的配置文件
+pub async fn ensure_core_accepts_file(path: &std::path::Path) -> Result<()> {
+    check_core_test_result(test_config_file_with_core(path).await)
+}
+
+fn check_core_test_result(result: Result<CoreTestResult>) -> Result<()> {
+    match result {
+        Ok(result) if result.valid => Ok(()),
+        Ok(result) => Err(anyhow::anyhow!(
+            "Mihomo rejected the config: {}",
+            result.errors.join("; ")
+        )),
+        Err(e) => {
+            warn!("Skipping mihomo config test: {}", e);
+            Ok(())
+        }
+    }
+}
+
 pub fn create_default_config() -> serde_json::Value {
     serde_json::json!({
         "port": 7890,
@@ -288,256 +438,442 @@ pub fn create_default_config() -> serde_json::Value {
 }
 
 pub async fn stop_mihomo() -> Result<()> {
+    stop_core(&MihomoApiClient::from_active_config().await?).await
+}
+
+/// 通过 `client` 指向的控制器停止核心
+async fn stop_core(client: &MihomoApiClient) -> Result<()> {
     // Try to gracefully stop mihomo via API first
-    if (send_shutdown_command().await).is_ok() {
+    let graceful = if (client.shutdown().await).is_ok() {
         info!("Sent shutdown command to mihomo, waiting for graceful shutdown...");
 
-        // 等待最多5秒让进程优雅关闭
-        for i in 0..10 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            if !is_mihomo_running().await {
-                info!("Mihomo stopped gracefully");
-                return Ok(());
-            }
-            if i == 4 {
-                info!("Mihomo still running after 2.5s, continuing to wait...");
-            }
-        }
+        // 等待控制器停止响应
+        client.wait_until_stopped(stop_timeout()).await
+    } else {
+        false
+    };
 
-        // 如果5秒后还在运行，强制杀进程
-        warn!("Mihomo did not stop gracefully, force killing...");
-        kill_mihomo_process().await?;
+    if graceful {
+        info!("Mihomo stopped gracefully");
     } else {
-        // API关闭失败，直接强制杀进程
-        info!("API shutdown failed, force killing mihomo process...");
-        kill_mihomo_process().await?;
+        warn!("Mihomo did not stop gracefully, terminating the tracked process...");
     }
 
-    Ok(())
-}
+    // 只结束本应用启动的进程（同时回收已退出的子进程），其他 mihomo 实例不受影响
+    let terminated = crate::process_manager::terminate(stop_timeout()).await?;
+
+    if !graceful && !terminated && client.is_healthy().await {
+        return Err(anyhow::anyhow!(
+            "Mihomo is still running but was not started by this app; \
+             use kill_all_mihomo_processes to stop it"
+        ));
+    }
 
-async fn send_shutdown_command() -> Result<()> {
-    let client = reqwest::Client::new();
-    client
-        .delete("http://127.0.0.1:9090/configs")
-        .send()
-        .await
-        .context("Failed to send shutdown command")?;
     Ok(())
 }
 
-async fn kill_mihomo_process() -> Result<()> {
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("taskkill")
-            .args(["/F", "/IM", "mihomo.exe"])
-            .output()
-            .context("Failed to kill mihomo process")?;
+/// 将已保存的 config.yaml 应用到运行中的核心
+///
+/// `previous` 为保存前的配置，用于判断是否修改了无法热重载的字段。
+pub async fn apply_config(previous: Option<&serde_json::Value>) -> ApplyResult {
+    // config.yaml 已是新配置，运行中的核心仍使用旧配置的控制器地址和 secret
+    let running = match previous {
+        Some(previous) => MihomoApiClient::new(&ControllerSettings::from_config(previous)),
+        None => MihomoApiClient::from_active_config().await,
+    };
+    let running = match running {
+        Ok(client) if client.is_healthy().await => client,
+        _ => {
+            return ApplyResult {
+                method: ApplyMethod::NotRunning,
+                success: true,
+                message: "Mihomo is not running, config will take effect on next start".to_string(),
+                restart_keys: Vec::new(),
+                process_id: None,
+                config_rewritten: false,
+            }
+        }
+    };
+
+    let config = match crate::config::load_config().await {
+        Ok(config) => config,
+        Err(e) => {
+            return apply_failed(
+                ApplyMethod::Skipped,
+                format!("Failed to load config: {}", e),
+            )
+        }
+    };
+
+    match crate::validator::validate_config(&config).await {
+        Ok(result) if !result.valid => {
+            return apply_failed(
+                ApplyMethod::Skipped,
+                format!("Config validation failed: {}", result.errors.join(", ")),
+            )
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Config validation error, applying anyway: {}", e),
     }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        Command::new("pkill")
-            .arg("mihomo")
-            .output()
-            .context("Failed to kill mihomo process")?;
+    let restart_keys = match previous {
+        Some(previous) => restart_required_keys(previous, &config),
+        None => Vec::new(),
+    };
+
+    if !restart_keys.is_empty() {
+        info!("Restarting mihomo to apply: {}", restart_keys.join(", "));
+        // 先用旧的控制器参数停止核心，start_mihomo 只能看到新配置
+        let restarted = async {
+            stop_core(&running).await?;
+            start_mihomo().await
+        };
+        return match restarted.await {
+            Ok(pid) => {
+                crate::selection::restore().await;
+                ApplyResult {
+                    method: ApplyMethod::Restart,
+                    success: true,
+                    message: format!("Mihomo restarted to apply {}", restart_keys.join(", ")),
+                    restart_keys,
+                    process_id: Some(pid),
+                    config_rewritten: false,
+                }
+            }
+            Err(e) => ApplyResult {
+                method: ApplyMethod::Restart,
+                success: false,
+                message: format!("Failed to restart mihomo: {}", e),
+                restart_keys,
+                process_id: None,
+                config_rewritten: false,
+            },
+        };
     }
 
-    Ok(())
-}
+    let reload = async {
+        let config_path = get_config_path()?;
+        running.reload_config(&config_path, true).await
+    };
 
-/// 检查mihomo是否正在运行
-pub async fn is_mihomo_running() -> bool {
-    // 尝试通过API检查
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(2))
-        .build();
-
-    if let Ok(client) = client {
-        if let Ok(response) = client.get("http://127.0.0.1:9090/version").send().await {
-            if response.status().is_success() {
-                return true;
+    match reload.await {
+        Ok(_) => {
+            info!("Config hot-reloaded into running mihomo");
+            // 重载会重建代理组，恢复用户之前的选择
+            crate::selection::restore().await;
+            ApplyResult {
+                method: ApplyMethod::HotReload,
+                success: true,
+                message: "Config reloaded".to_string(),
+                restart_keys,
+                process_id: None,
+                config_rewritten: false,
             }
         }
+        Err(e) => apply_failed(
+            ApplyMethod::HotReload,
+            format!("Failed to reload config: {}", e),
+        ),
     }
+}
 
-    false
+/// 新旧配置之间发生变化、需要重启核心的字段
+fn restart_required_keys(previous: &serde_json::Value, config: &serde_json::Value) -> Vec<String> {
+    RESTART_REQUIRED_KEYS
+        .iter()
+        .filter(|key| previous.get(**key) != config.get(**key))
+        .map(|key| key.to_string())
+        .collect()
 }
 
-pub async fn get_proxies() -> Result<serde_json::Value> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("http://127.0.0.1:9090/proxies")
-        .send()
-        .await
-        .context("Failed to fetch proxies")?;
+fn apply_failed(method: ApplyMethod, message: String) -> ApplyResult {
+    warn!("{}", message);
+    ApplyResult {
+        method,
+        success: false,
+        message,
+        restart_keys: Vec::new(),
+        process_id: None,
+        config_rewritten: false,
+    }
+}
 
-    let proxies: serde_json::Value = response
-        .json()
+fn ready_timeout() -> Duration {
+    let secs = crate::platform_config::PlatformConfig::common()
+        .map(|common| common.ready_timeout_secs)
+        .unwrap_or(15);
+    Duration::from_secs(secs)
+}
+
+/// 等待控制器响应，最长等待 ready_timeout_secs
+pub async fn wait_until_ready() -> Result<VersionInfo> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .wait_until_ready(ready_timeout())
         .await
-        .context("Failed to parse proxies response")?;
+}
 
-    Ok(proxies)
+fn stop_timeout() -> Duration {
+    let secs = crate::platform_config::PlatformConfig::common()
+        .map(|common| common.stop_timeout_secs)
+        .unwrap_or(5);
+    Duration::from_secs(secs)
 }
 
-pub async fn switch_proxy(group_name: &str, proxy_name: &str) -> Result<()> {
-    let client = reqwest::Client::new();
-    let mut body = std::collections::HashMap::new();
-    body.insert("name", proxy_name);
+/// 检查mihomo是否正在运行
+pub async fn is_mihomo_running() -> bool {
+    // 尝试通过API检查
+    match MihomoApiClient::from_active_config().await {
+        Ok(client) => client.is_healthy().await,
+        Err(_) => false,
+    }
+}
 
-    let response = client
-        .put(format!("http://127.0.0.1:9090/proxies/{}", group_name))
-        .json(&body)
-        .send()
+pub async fn get_proxies() -> Result<ProxiesResponse> {
+    MihomoApiClient::from_active_config().await?.proxies().await
+}
+
+pub async fn switch_proxy(group_name: &str, proxy_name: &str) -> Result<()> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .select_proxy(group_name, proxy_name)
         .await
-        .context("Failed to switch proxy")?;
+        .context("Failed to switch proxy")
+}
 
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "Failed to switch proxy: {}",
-            response.status()
-        ));
+/// 修改运行中核心的模式、日志级别等，`persist` 为 true 时同时写入 config.yaml
+pub async fn patch_runtime_config(patch: &RuntimeConfigPatch, persist: bool) -> Result<()> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .patch_configs(patch)
+        .await?;
+
+    if persist {
+        let patch = patch.clone();
+        crate::config::update_config(move |config| {
+            if let Some(mode) = patch.mode {
+                config["mode"] = serde_json::json!(mode.as_str());
+            }
+            if let Some(level) = patch.log_level {
+                config["log-level"] = serde_json::json!(level.as_str());
+            }
+            if let Some(allow_lan) = patch.allow_lan {
+                config["allow-lan"] = serde_json::json!(allow_lan);
+            }
+            Ok(())
+        })
+        .await
+        .context("Failed to persist runtime config")?;
     }
 
     Ok(())
 }
 
-#[allow(dead_code)]
-pub async fn get_traffic_stats() -> Result<serde_json::Value> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("http://127.0.0.1:9090/traffic")
-        .send()
+pub async fn list_providers() -> Result<ProvidersOverview> {
+    let client = MihomoApiClient::from_active_config().await?;
+
+    // 代理组内置的 Compatible provider 不是用户配置的，不展示
+    let mut proxy_providers: Vec<ProxyProviderInfo> = client
+        .proxy_providers()
+        .await?
+        .into_values()
+        .filter(|p| p.vehicle_type != "Compatible")
+        .collect();
+    proxy_providers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut rule_providers: Vec<RuleProviderInfo> =
+        client.rule_providers().await?.into_values().collect();
+    rule_providers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(ProvidersOverview {
+        proxy_providers,
+        rule_providers,
+    })
+}
+
+/// 更新 provider，未指定类别时按名称查找（优先代理 provider）
+pub async fn update_provider(name: &str, kind: Option<ProviderKind>) -> Result<ProviderKind> {
+    let client = MihomoApiClient::from_active_config().await?;
+
+    let kind = match kind {
+        Some(kind) => kind,
+        None if client.proxy_providers().await?.contains_key(name) => ProviderKind::Proxy,
+        None if client.rule_providers().await?.contains_key(name) => ProviderKind::Rule,
+        None => return Err(anyhow::anyhow!("Provider not found: {}", name)),
+    };
+
+    client.update_provider(kind, name).await?;
+    info!("Provider {} updated", name);
+    Ok(kind)
+}
+
+/// 对代理 provider 执行健康检查，返回检查后的节点状态
+pub async fn healthcheck_provider(name: &str) -> Result<ProxyProviderInfo> {
+    let client = MihomoApiClient::from_active_config().await?;
+    client.healthcheck_provider(name).await?;
+
+    client
+        .proxy_providers()
+        .await?
+        .remove(name)
+        .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", name))
+}
+
+pub async fn list_connections() -> Result<ConnectionsSnapshot> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .connections()
         .await
-        .context("Failed to fetch traffic stats")?;
+}
 
-    let stats: serde_json::Value = response
-        .json()
+pub async fn close_connection(id: &str) -> Result<()> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .close_connection(id)
         .await
-        .context("Failed to parse traffic stats")?;
+}
 
-    Ok(stats)
+pub async fn close_all_connections() -> Result<()> {
+    MihomoApiClient::from_active_config()
+        .await?
+        .close_all_connections()
+        .await
+}
+
+/// 关闭经过指定代理组的连接，使其在新节点上重新建立，返回关闭的数量
+pub async fn close_group_connections(group_name: &str) -> Result<usize> {
+    let client = MihomoApiClient::from_active_config().await?;
+    let snapshot = client.connections().await?;
+
+    let mut closed = 0;
+    for connection in snapshot
+        .connections
+        .iter()
+        .filter(|c| c.chains.iter().any(|chain| chain == group_name))
+    {
+        match client.close_connection(&connection.id).await {
+            Ok(_) => closed += 1,
+            Err(e) => warn!("Failed to close connection {}: {}", connection.id, e),
+        }
+    }
+
+    info!("Closed {} connections of group {}", closed, group_name);
+    Ok(closed)
 }
 
 pub async fn test_group_delay(group_name: &str) -> Result<()> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get(format!("http://127.0.0.1:9090/group/{}/delay", group_name))
-        .query(&[
-            ("timeout", "5000"),
-            ("url", "http://www.gstatic.com/generate_204"),
-        ])
-        .send()
+    MihomoApiClient::from_active_config()
+        .await?
+        .group_delay(group_name, &DelayTestOptions::default().query())
         .await
         .context("Failed to test group delay")?;
 
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "Failed to test group delay: {}",
-            response.status()
-        ));
-    }
-
     Ok(())
 }
 
 /// 测试单个代理节点的延迟
 pub async fn test_proxy_delay(proxy_name: &str, timeout: u32, test_url: &str) -> Result<u32> {
-    let client = reqwest::Client::new();
+    let client = MihomoApiClient::from_active_config().await?;
+    let result =
+        test_proxy_delay_with(&client, proxy_name, &DelayQuery::new(test_url, timeout)).await;
+
+    let sample = HashMap::from([(proxy_name.to_string(), result.as_ref().ok().copied())]);
+    crate::latency::record_results(&sample).await;
+
+    result
+}
+
+async fn test_proxy_delay_with(
+    client: &MihomoApiClient,
+    proxy_name: &str,
+    query: &DelayQuery,
+) -> Result<u32> {
     let response = client
-        .get(format!("http://127.0.0.1:9090/proxies/{}/delay", proxy_name))
-        .query(&[
-            ("timeout", timeout.to_string().as_str()),
-            ("url", test_url),
-        ])
-        .send()
+        .proxy_delay(proxy_name, query)
         .await
         .context("Failed to test proxy delay")?;
 
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "Failed to test proxy delay: {}",
-            response.status()
-        ));
-    }
-
-    let result: serde_json::Value = response.json().await?;
-    let delay = result["delay"]
-        .as_u64()
-        .ok_or_else(|| anyhow::anyhow!("Invalid delay response"))?;
-    
-    Ok(delay as u32)
+    Ok(response.delay)
 }
 
-/// 批量测试所有代理节点的延迟（并发测试）
-pub async fn test_all_groups_delay() -> Result<serde_json::Value> {
+/// 批量测试所有代理节点的延迟（并发测试），每个节点完成后回调 `on_progress`
+pub async fn test_all_groups_delay(
+    options: &DelayTestOptions,
+    on_progress: impl Fn(crate::events::DelayProgressEvent),
+) -> Result<DelayTestSummary> {
     info!("🚀 开始批量测试所有代理节点延迟");
 
     // 获取所有代理信息
-    let proxies = get_proxies().await?;
-    let proxy_map = proxies["proxies"]
-        .as_object()
-        .ok_or_else(|| anyhow::anyhow!("Invalid proxies response"))?;
+    let client = MihomoApiClient::from_active_config().await?;
+    let proxies = client.proxies().await?;
 
     // 找出所有真实的代理节点（排除代理组、Direct、Reject等）
-    let group_types = ["Selector", "URLTest", "Fallback", "LoadBalance"];
-    let exclude_types = ["Direct", "Reject", "Compatible", "Pass"];
-    let mut proxy_nodes = Vec::new();
-
-    for (name, proxy) in proxy_map {
-        if let Some(proxy_type) = proxy["type"].as_str() {
-            if !group_types.contains(&proxy_type) && !exclude_types.contains(&proxy_type) {
-                proxy_nodes.push(name.clone());
-            }
-        }
-    }
+    let proxy_nodes: Vec<String> = proxies
+        .proxies
+        .into_values()
+        .filter(|proxy| proxy.is_node())
+        .map(|proxy| proxy.name)
+        .collect();
 
     let total_nodes = proxy_nodes.len();
-    info!("📊 找到 {} 个代理节点", total_nodes);
+    info!(
+        "📊 找到 {} 个代理节点 (url: {}, timeout: {}ms, concurrency: {})",
+        total_nodes, options.test_url, options.timeout, options.concurrency
+    );
 
-    // 并发测试所有节点（使用合理的并发数避免过载）
-    let test_url = "http://www.gstatic.com/generate_204";
-    let timeout = 5000;
-    let mut results = std::collections::HashMap::new();
+    let query = options.query();
+    let mut results = HashMap::new();
     let mut success_count = 0;
 
-    // 使用 futures 并发测试，限制并发数为 10
+    // 使用 futures 并发测试，限制并发数避免过载
     use futures::stream::{self, StreamExt};
-    
-    let test_results: Vec<(String, Result<u32>)> = stream::iter(proxy_nodes.iter().cloned())
+
+    let mut test_results = stream::iter(proxy_nodes)
         .map(|proxy_name: String| {
+            let client = &client;
+            let query = &query;
             async move {
-                let result = test_proxy_delay(&proxy_name, timeout, test_url).await;
+                let result = test_proxy_delay_with(client, &proxy_name, query).await;
                 (proxy_name, result)
             }
         })
-        .buffer_unordered(10) // 限制并发数为10
-        .collect()
-        .await;
+        .buffer_unordered(options.concurrency.max(1));
 
-    // 处理测试结果
-    for (name, result) in test_results {
-        match result {
+    // 逐个处理完成的结果并推送进度
+    while let Some((name, result)) = test_results.next().await {
+        let (delay, error) = match result {
             Ok(delay) => {
                 info!("  ✓ {} - {}ms", name, delay);
-                results.insert(name, Some(delay as i64));
                 success_count += 1;
+                (Some(delay), None)
             }
             Err(e) => {
                 info!("  ✗ {} - {}", name, e);
-                results.insert(name, None);
+                (None, Some(format!("{:#}", e)))
             }
-        }
+        };
+
+        results.insert(name.clone(), delay);
+        on_progress(crate::events::DelayProgressEvent {
+            name,
+            delay,
+            error,
+            completed: results.len(),
+            total: total_nodes,
+            timestamp: crate::events::get_current_timestamp(),
+        });
     }
 
     info!("✅ 批量测速完成！成功: {}/{} 个节点", success_count, total_nodes);
 
-    Ok(serde_json::json!({
-        "total": total_nodes,
-        "tested": total_nodes,
-        "success": success_count,
-        "results": results
-    }))
+    crate::latency::record_results(&results).await;
+
+    Ok(DelayTestSummary {
+        total: total_nodes,
+        tested: results.len(),
+        success: success_count,
+        results,
+    })
 }
 
 fn get_config_path() -> Result<String> {
@@ -545,3 +881,50 @@ fn get_config_path() -> Result<String> {
     let config_path = crate::platform_config::PlatformPaths::config_file()?;
     Ok(config_path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_change_requires_restart() {
+        let previous = serde_json::json!({
+            "external-controller": "127.0.0.1:9090",
+            "secret": "old",
+            "mode": "rule",
+        });
+
+        let mut config = previous.clone();
+        config["mode"] = serde_json::json!("global");
+        assert!(restart_required_keys(&previous, &config).is_empty());
+
+        config["secret"] = serde_json::json!("new");
+        assert_eq!(restart_required_keys(&previous, &config), vec!["secret"]);
+
+        // 运行中的核心仍使用旧 secret，探测和停止必须用旧的控制器参数
+        let running = ControllerSettings::from_config(&previous);
+        assert_eq!(running.secret.as_deref(), Some("old"));
+        assert_ne!(running, ControllerSettings::from_config(&config));
+    }
+
+    #[test]
+    fn test_core_test_errors() {
+        let output = "time=\"2024-01-01T00:00:00Z\" level=info msg=\"Start initial configuration\"\n\
+                      time=\"2024-01-01T00:00:00Z\" level=fatal msg=\"Parse config error: proxy 0: missing type\"\n\
+                      configuration file /tmp/config.yaml test failed";
+        assert_eq!(
+            core_test_errors(output, false),
+            vec!["Parse config error: proxy 0: missing type"]
+        );
+
+        // 没有结构化日志时保留原始输出，去掉 "test failed" 行
+        let output = "yaml: line 3: mapping values are not allowed in this context\n\n\
+                      configuration file /tmp/config.yaml test failed";
+        assert_eq!(
+            core_test_errors(output, false),
+            vec!["yaml: line 3: mapping values are not allowed in this context"]
+        );
+
+        assert!(core_test_errors("configuration file test is successful", true).is_empty());
+    }
+}
@@ -56,6 +56,31 @@ pub struct CommonSettings {
     pub max_backups: usize,
     pub api_host: String,
     pub api_port: u16,
+    /// 校验 external-controller-tls 证书的 CA 文件（PEM），支持环境变量
+    #[serde(default)]
+    pub api_ca_cert: Option<String>,
+    /// 启动后等待控制器响应的最长时间（秒）
+    #[serde(default = "default_ready_timeout_secs")]
+    pub ready_timeout_secs: u64,
+    /// 停止时等待核心退出的最长时间（秒），超时后强制结束进程
+    #[serde(default = "default_stop_timeout_secs")]
+    pub stop_timeout_secs: u64,
+    /// 核心下载地址模板，支持 {version} {os} {arch} {ext} 占位符
+    #[serde(default = "default_core_download_url")]
+    pub core_download_url: String,
+}
+
+fn default_ready_timeout_secs() -> u64 {
+    15
+}
+
+fn default_stop_timeout_secs() -> u64 {
+    5
+}
+
+fn default_core_download_url() -> String {
+    "https://github.com/MetaCubeX/mihomo/releases/download/{version}/mihomo-{os}-{arch}-{version}.{ext}"
+        .to_string()
 }
 
 lazy_static! {
@@ -258,7 +283,6 @@ impl PlatformPaths {
     }
     
     /// 获取日志目录
-    #[allow(dead_code)]
     pub fn log_dir() -> Result<PathBuf> {
         let platform = PlatformConfig::current_platform()?;
         let path = PathResolver::resolve(&platform.log_dir)?;
@@ -267,7 +291,6 @@ impl PlatformPaths {
     }
     
     /// 获取系统路径列表
-    #[allow(dead_code)]
     pub fn system_paths() -> Result<Vec<PathBuf>> {
         let platform = PlatformConfig::current_platform()?;
         platform.system_paths
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use sysinfo::{Pid, Process, Signal, System};
+use tokio::process::Child;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 由本应用启动的核心进程
+struct ManagedProcess {
+    child: Child,
+    pid: u32,
+    exe: PathBuf,
+    config_path: String,
+    started_at: Instant,
+}
+
+/// 跟踪的核心进程的退出信息
+pub struct ExitInfo {
+    pub pid: u32,
+    /// 进程仍在运行（例如 API 卡死）时为 None
+    pub status: Option<ExitStatus>,
+    pub uptime: Duration,
+    pub exe: PathBuf,
+}
+
+impl ManagedProcess {
+    /// PID 可能已被系统复用，通过可执行文件路径和命令行确认仍是我们启动的核心
+    fn is_same_process(&self, process: &Process) -> bool {
+        // 无权读取可执行文件路径时只能依赖命令行判断
+        let exe_matches = process.exe().is_none_or(|exe| same_path(exe, &self.exe));
+        let cmd_matches = process.cmd().iter().any(|arg| arg == &self.config_path);
+        exe_matches && cmd_matches
+    }
+}
+
+lazy_static! {
+    static ref MANAGED_PROCESS: Mutex<Option<ManagedProcess>> = Mutex::new(None);
+    // 启动后未能就绪的核心不会被跟踪，保留其退出信息供崩溃记录使用
+    static ref FAILED_START: Mutex<Option<ExitInfo>> = Mutex::new(None);
+}
+
+/// 记录新启动的核心进程；之前跟踪的进程若仍在运行会先被结束，避免失去控制
+pub async fn track(child: Child, exe: &Path, config_path: &str) -> u32 {
+    let pid = child.id().unwrap_or(0);
+    FAILED_START.lock().await.take();
+
+    let mut guard = MANAGED_PROCESS.lock().await;
+    if let Some(mut previous) = guard.take() {
+        if let Ok(None) = previous.child.try_wait() {
+            warn!(
+                "Previously tracked mihomo (PID: {}) is still running, killing it",
+                previous.pid
+            );
+            if let Err(e) = previous.child.kill().await {
+                warn!("Failed to kill mihomo (PID: {}): {}", previous.pid, e);
+            }
+        }
+    }
+
+    *guard = Some(ManagedProcess {
+        child,
+        pid,
+        exe: exe.to_path_buf(),
+        config_path: config_path.to_string(),
+        started_at: Instant::now(),
+    });
+    pid
+}
+
+/// 结束本应用启动的核心：先发送 SIGTERM，`grace` 内未退出再发送 SIGKILL
+///
+/// 没有跟踪的进程或 PID 已不属于该核心时返回 false，不会影响其他 mihomo 实例。
+pub async fn terminate(grace: Duration) -> Result<bool> {
+    let Some(mut managed) = MANAGED_PROCESS.lock().await.take() else {
+        return Ok(false);
+    };
+
+    if let Ok(Some(status)) = managed.child.try_wait() {
+        info!("Mihomo (PID: {}) already exited: {}", managed.pid, status);
+        return Ok(true);
+    }
+
+    let mut system = System::new();
+    let pid = Pid::from_u32(managed.pid);
+    system.refresh_process(pid);
+
+    let Some(process) = system.process(pid) else {
+        return Ok(false);
+    };
+    if !managed.is_same_process(process) {
+        warn!(
+            "PID {} no longer belongs to the mihomo started by this app, not killing it",
+            managed.pid
+        );
+        return Ok(false);
+    }
+
+    // Windows 不支持 SIGTERM，直接强制结束
+    if process.kill_with(Signal::Term) == Some(true) {
+        info!("Sent SIGTERM to mihomo (PID: {})", managed.pid);
+        if tokio::time::timeout(grace, managed.child.wait())
+            .await
+            .is_ok()
+        {
+            return Ok(true);
+        }
+        warn!(
+            "Mihomo (PID: {}) did not exit after SIGTERM, sending SIGKILL",
+            managed.pid
+        );
+    }
+
+    managed
+        .child
+        .kill()
+        .await
+        .context("Failed to kill mihomo process")?;
+    info!("Killed mihomo (PID: {})", managed.pid);
+    Ok(true)
+}
+
+/// 记录启动失败（未就绪前退出或被结束）的核心进程
+pub async fn record_failed_start(info: ExitInfo) {
+    *FAILED_START.lock().await = Some(info);
+}
+
+/// 核心停止响应后读取跟踪进程的退出状态，最多等待 `wait`；已退出的进程不再跟踪
+///
+/// 没有跟踪的进程时返回最近一次启动失败的退出信息。
+pub async fn reap(wait: Duration) -> Option<ExitInfo> {
+    let mut guard = MANAGED_PROCESS.lock().await;
+    let Some(managed) = guard.as_mut() else {
+        return FAILED_START.lock().await.take();
+    };
+
+    let status = tokio::time::timeout(wait, managed.child.wait())
+        .await
+        .ok()
+        .and_then(Result::ok);
+    let info = ExitInfo {
+        pid: managed.pid,
+        status,
+        uptime: managed.started_at.elapsed(),
+        exe: managed.exe.clone(),
+    };
+
+    if status.is_some() {
+        guard.take();
+    }
+    Some(info)
+}
+
+/// 按平台配置的 kill_command 结束所有 mihomo 实例，包括系统服务或其他用户启动的核心
+pub async fn kill_all_instances() -> Result<()> {
+    let settings = crate::platform_config::PlatformConfig::current_platform()?;
+
+    let output = tokio::process::Command::new(&settings.kill_command)
+        .args(&settings.kill_args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", settings.kill_command))?;
+
+    // 没有匹配的进程时 pkill/taskkill 也会返回非零状态
+    if !output.status.success() {
+        warn!(
+            "{} exited with {}: {}",
+            settings.kill_command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    MANAGED_PROCESS.lock().await.take();
+    Ok(())
+}
+
+/// 比较两个路径是否指向同一文件；Linux 上被替换的可执行文件会带 " (deleted)" 后缀
+fn same_path(actual: &Path, expected: &Path) -> bool {
+    let actual_str = actual.to_string_lossy();
+    let actual = Path::new(actual_str.trim_end_matches(" (deleted)"));
+
+    match (actual.canonicalize(), expected.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => actual == expected,
+    }
+}
@@ -0,0 +1,265 @@
+use crate::core_log::{self, BackgroundLogWriter, CoreLogEntry, LogLevel};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+/// 保留的最近输出行数，用于报告启动失败或崩溃的上下文
+const TAIL_CAPACITY: usize = 200;
+
+/// 单独保留的 stderr 行数，stdout 刷屏时崩溃记录仍能拿到 panic 等信息
+const STDERR_CAPACITY: usize = 50;
+
+const OUTPUT_FILE_NAME: &str = "mihomo-output.log";
+
+/// 进程退出后等待读取任务读完剩余输出的最长时间
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// 子进程输出的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub line: String,
+    pub timestamp: String,
+}
+
+struct OutputStore {
+    tail: Mutex<VecDeque<OutputLine>>,
+    stderr: Mutex<VecDeque<String>>,
+    writer: Mutex<Option<BackgroundLogWriter>>,
+}
+
+impl OutputStore {
+    fn new() -> Self {
+        Self {
+            tail: Mutex::new(VecDeque::with_capacity(TAIL_CAPACITY)),
+            stderr: Mutex::new(VecDeque::with_capacity(STDERR_CAPACITY)),
+            writer: Mutex::new(None),
+        }
+    }
+
+    fn clear(&self) {
+        self.tail.lock().unwrap().clear();
+        self.stderr.lock().unwrap().clear();
+    }
+
+    fn push(&self, output: OutputLine) {
+        if output.stream == OutputStream::Stderr {
+            push_bounded(
+                &mut self.stderr.lock().unwrap(),
+                output.line.clone(),
+                STDERR_CAPACITY,
+            );
+        }
+        push_bounded(&mut self.tail.lock().unwrap(), output, TAIL_CAPACITY);
+    }
+}
+
+fn push_bounded<T>(ring: &mut VecDeque<T>, item: T, capacity: usize) {
+    if ring.len() >= capacity {
+        ring.pop_front();
+    }
+    ring.push_back(item);
+}
+
+lazy_static! {
+    static ref OUTPUT: OutputStore = OutputStore::new();
+}
+
+/// 正在读取的子进程输出
+pub struct OutputCapture {
+    readers: Vec<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl OutputCapture {
+    /// 接管子进程的 stdout/stderr，由后台任务持续读取，避免管道写满阻塞核心
+    pub fn attach(child: &mut tokio::process::Child) -> Self {
+        OUTPUT.clear();
+        ensure_writer();
+
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(OutputStream::Stdout, stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(OutputStream::Stderr, stderr));
+        }
+
+        Self { readers }
+    }
+
+    /// 进程退出后等待剩余输出读取完毕
+    pub async fn drain(self) {
+        let readers = futures::future::join_all(self.readers);
+        if tokio::time::timeout(DRAIN_TIMEOUT, readers).await.is_err() {
+            warn!("Timed out draining mihomo output");
+        }
+    }
+}
+
+/// 最近 `limit` 行输出（按时间正序）
+pub fn recent(limit: usize) -> Vec<OutputLine> {
+    let tail = OUTPUT.tail.lock().unwrap();
+    tail.iter()
+        .skip(tail.len().saturating_sub(limit))
+        .cloned()
+        .collect()
+}
+
+/// 最近的 stderr 行（按时间正序），最多 `STDERR_CAPACITY` 行
+pub fn recent_stderr() -> Vec<String> {
+    OUTPUT.stderr.lock().unwrap().iter().cloned().collect()
+}
+
+/// 把最近的输出拼成错误信息的上下文
+pub fn format_recent(limit: usize) -> String {
+    recent(limit)
+        .iter()
+        .map(|line| format!("[{}] {}", line.stream.as_str(), line.line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn ensure_writer() {
+    let mut writer = OUTPUT.writer.lock().unwrap();
+    if writer.is_some() {
+        return;
+    }
+
+    match crate::platform_config::PlatformPaths::log_dir()
+        .and_then(|dir| BackgroundLogWriter::open(dir, OUTPUT_FILE_NAME))
+    {
+        Ok(opened) => {
+            info!("Mihomo output file: {:?}", opened.path());
+            *writer = Some(opened);
+        }
+        Err(e) => warn!("Mihomo output file disabled: {}", e),
+    }
+}
+
+fn spawn_reader<R>(stream: OutputStream, reader: R) -> tauri::async_runtime::JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer).trim_end().to_string();
+                    if !line.is_empty() {
+                        record(stream, line);
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to read mihomo {}: {}", stream.as_str(), e);
+                    break;
+                }
+            }
+        }
+    })
+}
+
+fn record(stream: OutputStream, line: String) {
+    let output = OutputLine {
+        stream,
+        line,
+        timestamp: chrono::Local::now().to_rfc3339(),
+    };
+
+    if let Some(writer) = OUTPUT.writer.lock().unwrap().as_ref() {
+        writer.write_line(format!(
+            "{} [{}] {}",
+            output.timestamp,
+            stream.as_str(),
+            output.line
+        ));
+    }
+
+    // stdout 与 /logs 内容相同，订阅连接时不重复显示；stderr 通常是 panic 等致命错误，始终显示
+    match stream {
+        OutputStream::Stdout if !core_log::api_stream_connected() => {
+            let (level, message) = parse_log_line(&output.line);
+            core_log::record(CoreLogEntry::new(level, message));
+        }
+        OutputStream::Stderr => {
+            core_log::record(CoreLogEntry::new(LogLevel::Error, output.line.clone()));
+        }
+        _ => {}
+    }
+
+    OUTPUT.push(output);
+}
+
+/// 解析 mihomo 的 `time="..." level=info msg="..."` 格式，无法解析时原样返回
+pub fn parse_log_line(line: &str) -> (LogLevel, String) {
+    let level = line
+        .split_once("level=")
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .and_then(LogLevel::parse);
+
+    let message = line
+        .split_once("msg=\"")
+        .map(|(_, rest)| rest.strip_suffix('"').unwrap_or(rest).replace("\\\"", "\""));
+
+    match (level, message) {
+        (Some(level), Some(message)) => (level, message),
+        _ => (LogLevel::Info, line.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_kept_when_stdout_floods() {
+        let store = OutputStore::new();
+        for i in 0..60 {
+            store.push(OutputLine {
+                stream: OutputStream::Stderr,
+                line: i.to_string(),
+                timestamp: String::new(),
+            });
+        }
+        for i in 0..TAIL_CAPACITY {
+            store.push(OutputLine {
+                stream: OutputStream::Stdout,
+                line: format!("out {}", i),
+                timestamp: String::new(),
+            });
+        }
+
+        let tail = store.tail.lock().unwrap();
+        assert_eq!(tail.len(), TAIL_CAPACITY);
+        assert!(tail.iter().all(|line| line.stream == OutputStream::Stdout));
+
+        let stderr = store.stderr.lock().unwrap();
+        assert_eq!(stderr.len(), STDERR_CAPACITY);
+        assert_eq!(stderr.front().map(String::as_str), Some("10"));
+        assert_eq!(stderr.back().map(String::as_str), Some("59"));
+    }
+}
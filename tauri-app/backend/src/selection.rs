@@ -0,0 +1,156 @@
+use crate::api_client::MihomoApiClient;
+use crate::core_controller::wait_for_core;
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::watch;
+use tokio::time::Duration;
+use tracing::{debug, info, warn};
+
+const SELECTIONS_FILE_NAME: &str = "selections.json";
+
+/// 核心刚启动时 /proxies 可能尚未就绪，恢复前重试的次数与间隔
+const RESTORE_ATTEMPTS: u32 = 5;
+const RESTORE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    // 串行化对 selections.json 的读写
+    static ref SELECTIONS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn selections_path() -> Result<PathBuf> {
+    Ok(crate::platform_config::PlatformPaths::config_dir()?.join(SELECTIONS_FILE_NAME))
+}
+
+/// 读取已保存的选择（代理组 -> 节点）
+pub async fn load() -> HashMap<String, String> {
+    tokio::task::spawn_blocking(|| {
+        let _guard = SELECTIONS_LOCK.lock().unwrap();
+        read_selections().unwrap_or_default()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+fn read_selections() -> Result<HashMap<String, String>> {
+    let path = selections_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = std::fs::read_to_string(&path).context("Failed to read selections file")?;
+    serde_json::from_str(&content).context("Failed to parse selections file")
+}
+
+/// 记录用户在代理组中选择的节点，文件读写在阻塞线程池中进行
+pub async fn remember(group_name: &str, proxy_name: &str) {
+    let group = group_name.to_string();
+    let proxy = proxy_name.to_string();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let _guard = SELECTIONS_LOCK.lock().unwrap();
+
+        let mut selections = read_selections().unwrap_or_default();
+        selections.insert(group, proxy);
+        write_selections(&selections)
+    })
+    .await;
+
+    let error = match result {
+        Ok(Ok(())) => return,
+        Ok(Err(e)) => e.to_string(),
+        Err(e) => e.to_string(),
+    };
+    warn!(
+        "Failed to remember selection {} -> {}: {}",
+        group_name, proxy_name, error
+    );
+}
+
+fn write_selections(selections: &HashMap<String, String>) -> Result<()> {
+    let content =
+        serde_json::to_string_pretty(selections).context("Failed to serialize selections")?;
+    std::fs::write(selections_path()?, content).context("Failed to write selections file")
+}
+
+/// 将保存的选择重新应用到运行中的核心，跳过已不存在的代理组或节点
+///
+/// 返回实际切换的代理组数量。
+pub async fn restore() -> usize {
+    let selections = load().await;
+    if selections.is_empty() {
+        return 0;
+    }
+
+    let mut attempt = 0;
+    let (client, proxies) = loop {
+        attempt += 1;
+        let result = async {
+            let client = MihomoApiClient::from_active_config().await?;
+            let proxies = client.proxies().await?;
+            anyhow::Ok((client, proxies))
+        };
+
+        match result.await {
+            Ok(ready) => break ready,
+            Err(e) if attempt >= RESTORE_ATTEMPTS => {
+                warn!("Failed to restore proxy selections: {}", e);
+                return 0;
+            }
+            Err(_) => tokio::time::sleep(RESTORE_RETRY_DELAY).await,
+        }
+    };
+
+    let mut restored = 0;
+    for (group_name, proxy_name) in &selections {
+        let Some(group) = proxies.proxies.get(group_name) else {
+            debug!("Skipping selection for missing group {}", group_name);
+            continue;
+        };
+
+        // 只有 select 类型的代理组支持手动选择
+        if group.r#type != "Selector" || !group.all.contains(proxy_name) {
+            debug!(
+                "Skipping selection {} -> {}: node no longer available",
+                group_name, proxy_name
+            );
+            continue;
+        }
+
+        if group.now.as_deref() == Some(proxy_name.as_str()) {
+            continue;
+        }
+
+        match client.select_proxy(group_name, proxy_name).await {
+            Ok(_) => restored += 1,
+            Err(e) => warn!(
+                "Failed to restore selection {} -> {}: {}",
+                group_name, proxy_name, e
+            ),
+        }
+    }
+
+    if restored > 0 {
+        info!("Restored {} proxy group selections", restored);
+    }
+    restored
+}
+
+/// 每次核心进入 Running 状态时重新应用保存的选择
+pub fn spawn_restore_on_start(mut core_status: watch::Receiver<bool>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !wait_for_core(&mut core_status, true).await {
+                return;
+            }
+
+            restore().await;
+
+            if !wait_for_core(&mut core_status, false).await {
+                return;
+            }
+        }
+    });
+}
@@ -55,6 +55,9 @@ pub enum SubscriptionStatus {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SubscriptionStorage {
     pub subscriptions: HashMap<String, Subscription>,
+    /// 最近一次生成配置时节点名称到订阅 ID 的映射
+    #[serde(default)]
+    pub node_sources: HashMap<String, String>,
 }
 
 pub async fn add_subscription(
@@ -86,6 +89,14 @@ pub async fn add_subscription(
     Ok(())
 }
 
+/// 节点名称到所属订阅 ID 的映射
+pub async fn node_sources() -> HashMap<String, String> {
+    load_subscriptions()
+        .await
+        .map(|storage| storage.node_sources)
+        .unwrap_or_default()
+}
+
 pub async fn get_subscriptions() -> Result<Vec<Subscription>> {
     let storage = load_subscriptions().await.unwrap_or_default();
     Ok(storage.subscriptions.values().cloned().collect())
@@ -134,7 +145,6 @@ pub async fn update_subscription(id: &str) -> Result<()> {
                     }
 
                     info!("✓ 订阅更新成功，配置文件已生成");
-                    info!("提示: 配置已更新。如果Mihomo服务正在运行，请重启服务以应用更改。");
                 }
             }
             Err(e) => {
@@ -169,6 +179,7 @@ pub async fn generate_config_from_subscriptions(subscription_ids: Vec<String>) -
     let storage = load_subscriptions().await.unwrap_or_default();
     let mut all_proxies = Vec::new();
     let mut proxy_names = Vec::new();
+    let mut node_sources = HashMap::new();
 
     for id in subscription_ids {
         if let Some(subscription) = storage.subscriptions.get(&id) {
@@ -180,6 +191,7 @@ pub async fn generate_config_from_subscriptions(subscription_ids: Vec<String>) -
             for proxy in proxies {
                 if let Some(name) = proxy["name"].as_str() {
                     proxy_names.push(name.to_string());
+                    node_sources.insert(name.to_string(), id.clone());
                     all_proxies.push(proxy);
                 }
             }
@@ -190,36 +202,47 @@ pub async fn generate_config_from_subscriptions(subscription_ids: Vec<String>) -
         return Err(anyhow::anyhow!("没有找到任何代理节点"));
     }
 
-    // 使用原子更新防止竞态条件（内部会自动备份）
-    crate::config::update_config(|config| {
-        config["proxies"] = serde_json::json!(all_proxies);
-
-        // Create or update proxy groups
-        // PROXY group: manual selection with auto, all nodes, and DIRECT
-        let mut proxy_select_list = vec!["auto".to_string(), "DIRECT".to_string()];
-        proxy_select_list.extend(proxy_names.clone());
-
-        // auto group: automatic selection based on latency
-        let proxy_groups = vec![
-            serde_json::json!({
-                "name": "PROXY",
-                "type": "select",
-                "proxies": proxy_select_list
-            }),
-            serde_json::json!({
-                "name": "auto",
-                "type": "url-test",
-                "proxies": proxy_names.clone(),
-                "url": "http://www.gstatic.com/generate_204",
-                "interval": 300,
-                "tolerance": 50
-            }),
-        ];
-
-        config["proxy-groups"] = serde_json::json!(proxy_groups);
-
+    // Create or update proxy groups
+    // PROXY group: manual selection with auto, all nodes, and DIRECT
+    let mut proxy_select_list = vec!["auto".to_string(), "DIRECT".to_string()];
+    proxy_select_list.extend(proxy_names.clone());
+
+    // auto group: automatic selection based on latency
+    let proxy_groups = vec![
+        serde_json::json!({
+            "name": "PROXY",
+            "type": "select",
+            "proxies": proxy_select_list
+        }),
+        serde_json::json!({
+            "name": "auto",
+            "type": "url-test",
+            "proxies": proxy_names.clone(),
+            "url": "http://www.gstatic.com/generate_204",
+            "interval": 300,
+            "tolerance": 50
+        }),
+    ];
+
+    let proxies_value = serde_json::json!(all_proxies);
+    let groups_value = serde_json::json!(proxy_groups);
+
+    // 写入前先让核心检查生成的配置
+    let base = crate::config::load_config().await?;
+    let mut candidate = base.clone();
+    candidate["proxies"] = proxies_value;
+    candidate["proxy-groups"] = groups_value;
+    crate::mihomo::ensure_core_accepts(&candidate).await?;
+
+    // 使用原子更新防止竞态条件（内部会自动备份），只写入检查过的配置
+    crate::config::update_config(move |config| {
+        if *config != base {
+            return Err(anyhow::anyhow!("配置在检查期间被修改，请重试"));
+        }
+        *config = candidate;
         Ok(())
-    }).await?;
+    })
+    .await?;
 
     // 验证配置（在更新完成后）
     let config = crate::config::load_config().await?;
@@ -251,6 +274,11 @@ pub async fn generate_config_from_subscriptions(subscription_ids: Vec<String>) -
         }
     }
 
+    // 记录节点来源，延迟历史按订阅区分同名节点
+    let mut storage = load_subscriptions().await.unwrap_or_default();
+    storage.node_sources = node_sources;
+    save_subscriptions(&storage).await?;
+
     Ok(())
 }
 
@@ -509,9 +537,7 @@ async fn load_subscriptions() -> Result<SubscriptionStorage> {
     let path = get_subscriptions_path()?;
 
     if !path.exists() {
-        return Ok(SubscriptionStorage {
-            subscriptions: HashMap::new(),
-        });
+        return Ok(SubscriptionStorage::default());
     }
 
     let content = fs::read_to_string(&path).context("Failed to read subscriptions file")?;
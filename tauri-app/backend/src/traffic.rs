@@ -0,0 +1,73 @@
+use crate::api_client::{MihomoApiClient, TrafficSample};
+use crate::core_controller::wait_for_core;
+use anyhow::Result;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// 两次 traffic-update 事件之间的最小间隔
+const MIN_EMIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 流中断后重连前的等待时间
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// 启动流量监控后台任务
+///
+/// 核心健康时订阅 /traffic，核心停止后暂停，watchdog 报告恢复后自动重连。
+pub fn spawn_traffic_stream(app: tauri::AppHandle, mut core_status: watch::Receiver<bool>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // 等待核心可用
+            if !wait_for_core(&mut core_status, true).await {
+                return;
+            }
+
+            info!("Traffic stream connecting");
+
+            tokio::select! {
+                result = stream_traffic(&app) => {
+                    if let Err(e) = result {
+                        warn!("Traffic stream interrupted: {}", e);
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+                _ = wait_for_core(&mut core_status, false) => {
+                    info!("Mihomo is down, traffic stream paused");
+                }
+            }
+        }
+    });
+}
+
+/// 逐行读取 /traffic 流并按节流间隔发送事件
+async fn stream_traffic(app: &tauri::AppHandle) -> Result<()> {
+    let client = MihomoApiClient::from_active_config().await?;
+    let mut lines = client.stream_lines("/traffic").await?;
+    let mut last_emit: Option<Instant> = None;
+
+    while let Some(line) = lines.next_line().await? {
+        let sample: TrafficSample = match serde_json::from_str(&line) {
+            Ok(sample) => sample,
+            Err(e) => {
+                debug!("Skipping malformed traffic sample {:?}: {}", line, e);
+                continue;
+            }
+        };
+
+        if last_emit.is_some_and(|t| t.elapsed() < MIN_EMIT_INTERVAL) {
+            continue;
+        }
+        last_emit = Some(Instant::now());
+
+        crate::events::emit_traffic_update(
+            app,
+            crate::events::TrafficEvent {
+                up: sample.up,
+                down: sample.down,
+                timestamp: crate::events::get_current_timestamp(),
+            },
+        );
+    }
+
+    Err(anyhow::anyhow!("Traffic stream closed by mihomo"))
+}
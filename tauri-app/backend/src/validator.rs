@@ -30,7 +30,10 @@ pub async fn validate_config(config: &serde_json::Value) -> Result<ValidationRes
     validate_proxy_groups(config, &mut errors, &mut warnings);
 
     // 6. 验证规则
-    validate_rules(config, &mut warnings);
+    validate_rules(config, &mut errors, &mut warnings);
+
+    // 7. 验证 proxy-providers / rule-providers
+    validate_providers(config, &mut errors);
 
     let valid = errors.is_empty();
 
@@ -107,9 +110,14 @@ fn validate_proxies(
     errors: &mut Vec<String>,
     warnings: &mut Vec<String>,
 ) {
+    // 节点全部来自 proxy-providers 时允许 proxies 为空
+    let has_providers = !provider_names(config, "proxy-providers").is_empty();
+
     if let Some(proxies) = config.get("proxies").and_then(|v| v.as_array()) {
         if proxies.is_empty() {
-            warnings.push("没有配置代理节点".to_string());
+            if !has_providers {
+                warnings.push("没有配置代理节点".to_string());
+            }
             return;
         }
 
@@ -150,7 +158,7 @@ fn validate_proxies(
                 }
             }
         }
-    } else {
+    } else if !has_providers {
         warnings.push("没有配置代理节点".to_string());
     }
 }
@@ -167,6 +175,7 @@ fn validate_proxy_groups(
         }
 
         let mut group_names = HashSet::new();
+        let providers = provider_names(config, "proxy-providers");
         let proxy_names: HashSet<String> =
             if let Some(proxies) = config.get("proxies").and_then(|v| v.as_array()) {
                 proxies
@@ -191,8 +200,30 @@ fn validate_proxy_groups(
                 errors.push(format!("代理组 #{} 缺少type字段", idx));
             }
 
-            if group.get("proxies").is_none() {
-                errors.push(format!("代理组 #{} 缺少proxies字段", idx));
+            // 节点也可以来自 use 引用的 provider 或 include-all
+            let has_members = [
+                "proxies",
+                "use",
+                "include-all",
+                "include-all-proxies",
+                "include-all-providers",
+            ]
+            .iter()
+            .any(|key| group.get(key).is_some());
+            if !has_members {
+                errors.push(format!("代理组 #{} 缺少proxies或use字段", idx));
+            }
+
+            // 检查 use 引用的 provider 是否存在
+            if let Some(uses) = group.get("use").and_then(|v| v.as_array()) {
+                for provider_ref in uses.iter().filter_map(|v| v.as_str()) {
+                    if !providers.contains(provider_ref) {
+                        errors.push(format!(
+                            "代理组 #{} 引用了不存在的proxy-provider: {}",
+                            idx, provider_ref
+                        ));
+                    }
+                }
             }
 
             // 检查名称重复
@@ -238,13 +269,30 @@ fn validate_proxy_groups(
     }
 }
 
-fn validate_rules(config: &serde_json::Value, warnings: &mut Vec<String>) {
+fn validate_rules(
+    config: &serde_json::Value,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
     if let Some(rules) = config.get("rules").and_then(|v| v.as_array()) {
         if rules.is_empty() {
             warnings.push("没有配置路由规则".to_string());
             return;
         }
 
+        // 检查 RULE-SET 引用的 rule-provider 是否存在
+        let rule_providers = provider_names(config, "rule-providers");
+        for rule_str in rules.iter().filter_map(|r| r.as_str()) {
+            let mut parts = rule_str.split(',').map(|p| p.trim());
+            if parts.next() == Some("RULE-SET") {
+                if let Some(provider) = parts.next() {
+                    if !rule_providers.contains(provider) {
+                        errors.push(format!("规则引用了不存在的rule-provider: {}", provider));
+                    }
+                }
+            }
+        }
+
         // 检查是否有MATCH规则
         let has_match = rules.iter().any(|r| {
             if let Some(rule_str) = r.as_str() {
@@ -261,3 +309,116 @@ fn validate_rules(config: &serde_json::Value, warnings: &mut Vec<String>) {
         warnings.push("没有配置路由规则".to_string());
     }
 }
+
+fn validate_providers(config: &serde_json::Value, errors: &mut Vec<String>) {
+    if let Some(providers) = config.get("proxy-providers").and_then(|v| v.as_object()) {
+        for (name, provider) in providers {
+            validate_provider_source(name, provider, errors);
+        }
+    }
+
+    if let Some(providers) = config.get("rule-providers").and_then(|v| v.as_object()) {
+        for (name, provider) in providers {
+            validate_provider_source(name, provider, errors);
+
+            if let Some(behavior) = provider.get("behavior").and_then(|v| v.as_str()) {
+                if !["domain", "ipcidr", "classical"].contains(&behavior) {
+                    errors.push(format!(
+                        "rule-provider {} 使用了无效的behavior: {}",
+                        name, behavior
+                    ));
+                }
+            } else {
+                errors.push(format!("rule-provider {} 缺少behavior字段", name));
+            }
+        }
+    }
+}
+
+/// 检查 provider 的来源：http 需要 url，file 需要 path，inline 需要 payload
+fn validate_provider_source(name: &str, provider: &serde_json::Value, errors: &mut Vec<String>) {
+    let required = match provider.get("type").and_then(|v| v.as_str()) {
+        Some("http") => "url",
+        Some("file") => "path",
+        Some("inline") => "payload",
+        Some(other) => {
+            errors.push(format!("provider {} 使用了无效的类型: {}", name, other));
+            return;
+        }
+        None => {
+            errors.push(format!("provider {} 缺少type字段", name));
+            return;
+        }
+    };
+
+    if provider.get(required).is_none() {
+        errors.push(format!("provider {} 缺少{}字段", name, required));
+    }
+}
+
+/// 配置中某类 provider 的名称集合
+fn provider_names(config: &serde_json::Value, key: &str) -> HashSet<String> {
+    config
+        .get(key)
+        .and_then(|v| v.as_object())
+        .map(|providers| providers.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors_of(config: serde_json::Value) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_proxy_groups(&config, &mut errors, &mut warnings);
+        validate_rules(&config, &mut errors, &mut warnings);
+        validate_providers(&config, &mut errors);
+        errors
+    }
+
+    #[test]
+    fn test_rule_set_requires_rule_provider() {
+        let errors = errors_of(serde_json::json!({
+            "rule-providers": {
+                "ads": {"type": "http", "url": "https://example.com/ads.yaml", "behavior": "domain"},
+            },
+            "rules": ["RULE-SET,ads,REJECT", "RULE-SET, missing ,DIRECT", "MATCH,DIRECT"],
+        }));
+        assert_eq!(errors, vec!["规则引用了不存在的rule-provider: missing"]);
+    }
+
+    #[test]
+    fn test_group_use_requires_proxy_provider() {
+        let errors = errors_of(serde_json::json!({
+            "proxy-providers": {
+                "airport": {"type": "http", "url": "https://example.com/sub"},
+            },
+            "proxy-groups": [
+                {"name": "PROXY", "type": "select", "use": ["airport", "ghost"]},
+            ],
+        }));
+        assert_eq!(
+            errors,
+            vec!["代理组 #0 引用了不存在的proxy-provider: ghost"]
+        );
+    }
+
+    #[test]
+    fn test_provider_required_fields() {
+        let errors = errors_of(serde_json::json!({
+            "proxy-providers": {
+                "remote": {"type": "http"},
+                "local": {"type": "file"},
+            },
+            "rule-providers": {
+                "rules": {"type": "http", "url": "https://example.com/rules.yaml"},
+            },
+        }));
+        assert!(errors.contains(&"provider remote 缺少url字段".to_string()));
+        assert!(errors.contains(&"provider local 缺少path字段".to_string()));
+        assert!(errors.contains(&"rule-provider rules 缺少behavior字段".to_string()));
+        assert_eq!(errors.len(), 3);
+    }
+}
@@ -1,67 +1,234 @@
+use crate::core_controller::{CoreController, CoreState};
+use crate::data_path::{DataPathState, DataPathStatus};
+use crate::failover::FailoverPolicy;
+use crate::platform_config::PlatformPaths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
+use tokio::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
-pub struct ProcessWatchdog {
-    process_id: Arc<RwLock<Option<u32>>>,
-    auto_restart: Arc<RwLock<bool>>,
-    app_handle: tauri::AppHandle,
-    monitoring: Arc<RwLock<bool>>,
-    last_known_status: Arc<RwLock<bool>>,
-    manual_stop: Arc<RwLock<bool>>, // 标记是否为手动停止
+const POLICY_FILE_NAME: &str = "watchdog-policy.json";
+
+/// 数据通路探测持续失败时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataPathAction {
+    /// 只在状态事件中报告 failing
+    Alert,
+    /// 按重启策略重启核心
+    Restart,
 }
 
-/// 检查 mihomo API 是否可访问（健康检查）
-async fn check_mihomo_api_health() -> bool {
-    match reqwest::Client::builder()
-        .timeout(Duration::from_secs(2))
-        .build()
-    {
-        Ok(client) => match client.get("http://127.0.0.1:9090/version").send().await {
-            Ok(response) => response.status().is_success(),
-            Err(_) => false,
-        },
-        Err(_) => false,
+/// watchdog 的健康检查与自动重启策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogPolicy {
+    /// 健康检查间隔（秒）
+    pub interval_secs: u64,
+    /// 连续检查失败多少次后才重启
+    pub failure_threshold: u32,
+    /// 第一次重启前的等待时间（毫秒）
+    pub initial_backoff_ms: u64,
+    /// 重启等待时间的上限（毫秒）
+    pub max_backoff_ms: u64,
+    /// 每次重启后等待时间的倍数
+    pub backoff_multiplier: f64,
+    /// 等待时间的随机浮动比例，0.2 表示 ±20%
+    pub jitter: f64,
+    /// 放弃前的最大重启次数
+    pub max_attempts: u32,
+    /// 核心连续正常运行这么久后重置重启次数；放弃后经过同样时间再开始新一轮，0 表示不再自动重试
+    pub cooldown_secs: u64,
+    /// 是否通过 mixed-port / port 请求 probe_url 检查数据通路
+    pub data_path_probe: bool,
+    pub probe_url: String,
+    pub probe_timeout_ms: u64,
+    /// 连续探测失败多少次后视为 failing
+    pub data_path_failure_threshold: u32,
+    pub data_path_action: DataPathAction,
+    /// select 代理组的自动故障转移
+    pub failover: FailoverPolicy,
+}
+
+impl Default for WatchdogPolicy {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3,
+            failure_threshold: 1,
+            initial_backoff_ms: 2000,
+            max_backoff_ms: 60_000,
+            backoff_multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: 5,
+            cooldown_secs: 60,
+            data_path_probe: false,
+            probe_url: crate::mihomo::DEFAULT_TEST_URL.to_string(),
+            probe_timeout_ms: 5000,
+            data_path_failure_threshold: 3,
+            data_path_action: DataPathAction::Alert,
+            failover: FailoverPolicy::default(),
+        }
     }
 }
 
+impl WatchdogPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.interval_secs == 0 {
+            anyhow::bail!("interval_secs must be at least 1");
+        }
+        if self.failure_threshold == 0 {
+            anyhow::bail!("failure_threshold must be at least 1");
+        }
+        if self.max_backoff_ms < self.initial_backoff_ms {
+            anyhow::bail!("max_backoff_ms must not be less than initial_backoff_ms");
+        }
+        if !(self.backoff_multiplier >= 1.0 && self.backoff_multiplier.is_finite()) {
+            anyhow::bail!("backoff_multiplier must be a finite number >= 1");
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            anyhow::bail!("jitter must be between 0 and 1");
+        }
+        if self.data_path_probe {
+            let url = reqwest::Url::parse(&self.probe_url).context("Invalid probe_url")?;
+            if !matches!(url.scheme(), "http" | "https") {
+                anyhow::bail!("probe_url must be an http or https URL");
+            }
+            if self.probe_timeout_ms == 0 {
+                anyhow::bail!("probe_timeout_ms must be at least 1");
+            }
+            if self.data_path_failure_threshold == 0 {
+                anyhow::bail!("data_path_failure_threshold must be at least 1");
+            }
+        }
+        self.failover.validate()
+    }
+
+    /// 第 `attempt` 次重启（从 1 开始）前的等待时间，不含随机浮动
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let delay = self.initial_backoff_ms as f64 * self.backoff_multiplier.powi(exponent);
+        Duration::from_millis(delay.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    fn backoff_with_jitter(&self, attempt: u32) -> Duration {
+        // 在 [1 - jitter, 1 + jitter] 内浮动，避免多个实例同时重启
+        let factor = 1.0 + self.jitter * (random_unit() * 2.0 - 1.0);
+        self.backoff(attempt).mul_f64(factor.max(0.0))
+    }
+
+    fn cooldown(&self) -> Option<Duration> {
+        (self.cooldown_secs > 0).then(|| Duration::from_secs(self.cooldown_secs))
+    }
+}
+
+/// [0, 1) 内的随机数，只用于抖动，不需要密码学强度
+fn random_unit() -> f64 {
+    // 取 UUID 低 53 位，避开固定的版本和变体位
+    const MANTISSA: u64 = 1 << 53;
+    (uuid::Uuid::new_v4().as_u128() as u64 % MANTISSA) as f64 / MANTISSA as f64
+}
+
+fn policy_path() -> Result<PathBuf> {
+    Ok(PlatformPaths::config_dir()?.join(POLICY_FILE_NAME))
+}
+
+/// 读取已保存的策略，文件不存在时使用默认值；文件读写在阻塞线程池中进行
+pub async fn load_policy() -> Result<WatchdogPolicy> {
+    tokio::task::spawn_blocking(read_policy)
+        .await
+        .context("Watchdog policy task failed")?
+}
+
+fn read_policy() -> Result<WatchdogPolicy> {
+    let path = policy_path()?;
+    if !path.exists() {
+        return Ok(WatchdogPolicy::default());
+    }
+
+    let content = std::fs::read_to_string(&path).context("Failed to read watchdog policy")?;
+    let policy: WatchdogPolicy =
+        serde_json::from_str(&content).context("Failed to parse watchdog policy")?;
+    policy.validate()?;
+    Ok(policy)
+}
+
+async fn save_policy(policy: &WatchdogPolicy) -> Result<()> {
+    let content =
+        serde_json::to_string_pretty(policy).context("Failed to serialize watchdog policy")?;
+
+    tokio::task::spawn_blocking(move || {
+        let path = policy_path()?;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, content).context("Failed to write watchdog policy")?;
+        std::fs::rename(&tmp_path, &path).context("Failed to replace watchdog policy")
+    })
+    .await
+    .context("Watchdog policy task failed")?
+}
+
+/// watchdog 当前状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogStatus {
+    /// 监控任务是否在运行，暂停时仍为 true，见 `paused`
+    pub monitoring: bool,
+    pub paused: bool,
+    pub auto_restart: bool,
+    /// 最近一次健康检查的时间
+    pub last_check: Option<u64>,
+    /// 连续健康检查失败次数
+    pub consecutive_failures: u32,
+    /// 当前这一轮的重启次数，核心稳定运行 cooldown 后清零
+    pub restart_attempts: u32,
+    pub max_attempts: u32,
+    /// 已达到最大重启次数，等待 cooldown 或重新启用
+    pub gave_up: bool,
+    /// 核心被手动停止，watchdog 不会重启它
+    pub manual_stop: bool,
+    pub core_state: CoreState,
+}
+
+/// 监控循环中的重启计数
+#[derive(Debug, Clone, Default)]
+struct RestartCounters {
+    failures: u32,
+    attempts: u32,
+    gave_up_at: Option<Instant>,
+    last_check: Option<u64>,
+}
+
+pub struct ProcessWatchdog {
+    app_handle: tauri::AppHandle,
+    controller: Arc<CoreController>,
+    auto_restart: Arc<RwLock<bool>>,
+    monitoring: Arc<RwLock<bool>>,
+    paused: Arc<RwLock<bool>>,
+    /// 请求监控循环在下一次检查前清空重启计数
+    rearm: Arc<RwLock<bool>>,
+    /// 监控循环每次检查后发布的计数
+    counters: Arc<RwLock<RestartCounters>>,
+    policy: Arc<RwLock<WatchdogPolicy>>,
+}
+
 impl ProcessWatchdog {
-    pub fn new(app_handle: tauri::AppHandle) -> Self {
+    /// `policy` 为启动时通过 [`load_policy`] 读取的策略
+    pub fn new(
+        app_handle: tauri::AppHandle,
+        controller: Arc<CoreController>,
+        policy: WatchdogPolicy,
+    ) -> Self {
         Self {
-            process_id: Arc::new(RwLock::new(None)),
-            auto_restart: Arc::new(RwLock::new(true)),
             app_handle,
+            controller,
+            auto_restart: Arc::new(RwLock::new(true)),
             monitoring: Arc::new(RwLock::new(false)),
-            last_known_status: Arc::new(RwLock::new(false)),
-            manual_stop: Arc::new(RwLock::new(false)),
-        }
-    }
-
-    pub async fn set_process(&self, pid: u32) {
-        let mut process_id = self.process_id.write().await;
-        *process_id = Some(pid);
-        // 清除手动停止标志（因为服务正在启动）
-        let mut manual_stop = self.manual_stop.write().await;
-        *manual_stop = false;
-        info!("Watchdog tracking process: {}", pid);
-    }
-
-    pub async fn clear_process(&self) {
-        let mut process_id = self.process_id.write().await;
-        *process_id = None;
-        drop(process_id);
-        
-        // 设置手动停止标志
-        let mut manual_stop = self.manual_stop.write().await;
-        *manual_stop = true;
-        drop(manual_stop);
-        
-        // 同时禁用自动重启，防止竞态条件
-        let mut auto_restart = self.auto_restart.write().await;
-        *auto_restart = false;
-        
-        info!("Watchdog cleared process tracking (manual stop, auto-restart disabled)");
+            paused: Arc::new(RwLock::new(false)),
+            rearm: Arc::new(RwLock::new(false)),
+            counters: Arc::new(RwLock::new(RestartCounters::default())),
+            policy: Arc::new(RwLock::new(policy)),
+        }
     }
 
     pub async fn set_auto_restart(&self, enabled: bool) {
@@ -75,18 +242,57 @@ impl ProcessWatchdog {
         *auto_restart
     }
 
-    /// 重新启用自动重启（用于用户手动停止后想重新启用）
-    #[allow(dead_code)]
-    pub async fn enable_auto_restart(&self) {
-        let mut auto_restart = self.auto_restart.write().await;
-        *auto_restart = true;
-        drop(auto_restart);
-        
-        // 清除手动停止标志
-        let mut manual_stop = self.manual_stop.write().await;
-        *manual_stop = false;
-        
-        info!("Auto-restart re-enabled (manual stop flag cleared)");
+    /// 重新启用自动重启，并清空重启计数和放弃状态，崩溃的核心会在下一次检查时被重启
+    pub async fn rearm_auto_restart(&self) {
+        self.set_auto_restart(true).await;
+        self.controller.clear_manual_stop();
+        *self.rearm.write().await = true;
+        *self.counters.write().await = RestartCounters::default();
+        info!("Auto-restart re-armed (restart attempts cleared)");
+    }
+
+    /// 暂停健康检查、自动重启和故障转移，不影响核心本身
+    pub async fn pause(&self) {
+        *self.paused.write().await = true;
+        info!("Watchdog paused");
+    }
+
+    pub async fn resume(&self) {
+        *self.paused.write().await = false;
+        info!("Watchdog resumed");
+    }
+
+    pub async fn status(&self) -> WatchdogStatus {
+        let monitoring = *self.monitoring.read().await;
+        let paused = *self.paused.read().await;
+        let counters = self.counters.read().await.clone();
+        let core_state = self.controller.state();
+
+        WatchdogStatus {
+            monitoring,
+            paused,
+            auto_restart: self.get_auto_restart().await,
+            last_check: counters.last_check,
+            consecutive_failures: counters.failures,
+            restart_attempts: counters.attempts,
+            max_attempts: self.policy.read().await.max_attempts,
+            gave_up: counters.gave_up_at.is_some(),
+            manual_stop: self.controller.manually_stopped(),
+            core_state,
+        }
+    }
+
+    pub async fn get_policy(&self) -> WatchdogPolicy {
+        self.policy.read().await.clone()
+    }
+
+    /// 校验并保存新策略，下一次检查起生效
+    pub async fn set_policy(&self, policy: WatchdogPolicy) -> Result<()> {
+        policy.validate()?;
+        save_policy(&policy).await?;
+        info!("Watchdog policy updated: {:?}", policy);
+        *self.policy.write().await = policy;
+        Ok(())
     }
 
     pub async fn start_monitoring(&self) {
@@ -98,24 +304,33 @@ impl ProcessWatchdog {
         *monitoring = true;
         drop(monitoring);
 
-        let process_id = self.process_id.clone();
-        let auto_restart = self.auto_restart.clone();
-        let app_handle = self.app_handle.clone();
+        let mut monitor = Monitor {
+            app_handle: self.app_handle.clone(),
+            controller: self.controller.clone(),
+            auto_restart: self.auto_restart.clone(),
+            paused: self.paused.clone(),
+            counters: RestartCounters::default(),
+        };
         let monitoring_flag = self.monitoring.clone();
-        let last_known_status = self.last_known_status.clone();
-        let manual_stop = self.manual_stop.clone();
-
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(3));
-            let mut restart_count = 0;
-            let max_restart_attempts = 5;
-            let restart_window = Duration::from_secs(60);
-            let mut last_restart_time = std::time::Instant::now();
-
+        let paused = self.paused.clone();
+        let rearm = self.rearm.clone();
+        let published = self.counters.clone();
+        let policy_lock = self.policy.clone();
+
+        crate::failover::spawn(
+            self.app_handle.clone(),
+            self.controller.clone(),
+            self.policy.clone(),
+            self.monitoring.clone(),
+            self.paused.clone(),
+        );
+
+        let monitor_loop = async move {
             info!("Watchdog monitoring started (API health check mode)");
 
             loop {
-                interval.tick().await;
+                let policy = policy_lock.read().await.clone();
+                tokio::time::sleep(Duration::from_secs(policy.interval_secs)).await;
 
                 let should_continue = {
                     let monitoring = monitoring_flag.read().await;
@@ -127,99 +342,277 @@ impl ProcessWatchdog {
                     break;
                 }
 
-                // 使用 API 健康检查替代进程检查
-                let api_healthy = check_mihomo_api_health().await;
+                if *paused.read().await {
+                    continue;
+                }
+
+                if std::mem::take(&mut *rearm.write().await) {
+                    monitor.counters = RestartCounters::default();
+                }
+
+                monitor.check(&policy).await;
+                *published.write().await = monitor.counters.clone();
+            }
+        };
+
+        // 循环结束（包括 panic）后清除标记，之后可以再次 start_monitoring
+        let monitoring_flag = self.monitoring.clone();
+        tokio::spawn(async move {
+            if let Err(e) = tokio::spawn(monitor_loop).await {
+                error!("Watchdog monitoring task failed: {}", e);
+            }
+            *monitoring_flag.write().await = false;
+        });
+    }
+}
+
+/// 监控循环的状态
+struct Monitor {
+    app_handle: tauri::AppHandle,
+    controller: Arc<CoreController>,
+    auto_restart: Arc<RwLock<bool>>,
+    paused: Arc<RwLock<bool>>,
+    counters: RestartCounters,
+}
+
+impl Monitor {
+    /// 执行一次健康检查，必要时按策略重启核心
+    async fn check(&mut self, policy: &WatchdogPolicy) {
+        let controller = self.controller.clone();
+        self.counters.last_check = Some(crate::events::get_current_timestamp());
+
+        if !policy.data_path_probe {
+            controller.set_data_path(DataPathStatus::new(DataPathState::Disabled));
+        }
 
-                let last_status = {
-                    let status = last_known_status.read().await;
-                    *status
+        // 由控制器根据 API 健康检查更新状态；手动停止的核心处于 Stopped，不会被重启
+        let reason = match controller.check_health().await {
+            CoreState::Crashed => {
+                self.counters.failures += 1;
+                RestartReason::Crashed
+            }
+            CoreState::Running => {
+                self.counters.failures = 0;
+                let data_path = if policy.data_path_probe {
+                    check_data_path(&controller, policy).await.state
+                } else {
+                    DataPathState::Disabled
                 };
 
-                // 只在状态变化时发送事件
-                if api_healthy != last_status {
-                    info!("Mihomo status changed: {} -> {}", last_status, api_healthy);
-
-                    {
-                        let mut status = last_known_status.write().await;
-                        *status = api_healthy;
-                    }
-
-                    crate::events::emit_mihomo_status(
-                        &app_handle,
-                        crate::events::MihomoStatusEvent {
-                            running: api_healthy,
-                            process_id: None,
-                            timestamp: crate::events::get_current_timestamp(),
-                        },
-                    );
+                if data_path != DataPathState::Failing {
+                    self.reset_when_stable(policy);
+                    return;
+                }
+                if policy.data_path_action != DataPathAction::Restart {
+                    return;
+                }
+                RestartReason::DataPath
+            }
+            _ => {
+                self.counters.failures = 0;
+                return;
+            }
+        };
+
+        let should_restart = {
+            let restart_lock = self.auto_restart.read().await;
+            *restart_lock
+        };
+        if !should_restart
+            || (reason == RestartReason::Crashed
+                && self.counters.failures < policy.failure_threshold)
+        {
+            return;
+        }
 
-                    // 如果服务停止且启用了自动重启
-                    if !api_healthy {
-                        warn!("Mihomo API is not responding, service may have stopped");
-
-                        {
-                            let mut pid_lock = process_id.write().await;
-                            *pid_lock = None;
-                        }
-
-                        let should_restart = {
-                            let restart_lock = auto_restart.read().await;
-                            *restart_lock
-                        };
-
-                        // 检查是否为手动停止
-                        let is_manual_stop = {
-                            let manual_stop_lock = manual_stop.read().await;
-                            *manual_stop_lock
-                        };
-
-                        if should_restart && !is_manual_stop {
-                            let now = std::time::Instant::now();
-                            if now.duration_since(last_restart_time) > restart_window {
-                                restart_count = 0;
-                            }
-
-                            if restart_count >= max_restart_attempts {
-                                error!(
-                                    "Max restart attempts ({}) reached within {} seconds, giving up",
-                                    max_restart_attempts,
-                                    restart_window.as_secs()
-                                );
-                                continue;
-                            }
-
-                            restart_count += 1;
-                            last_restart_time = now;
-
-                            info!(
-                                "Auto-restarting mihomo (attempt {}/{})",
-                                restart_count, max_restart_attempts
-                            );
-
-                            tokio::time::sleep(Duration::from_secs(2)).await;
-
-                            match crate::mihomo::start_mihomo().await {
-                                Ok(new_pid) => {
-                                    info!("Mihomo restarted successfully with PID: {}", new_pid);
-
-                                    {
-                                        let mut pid_lock = process_id.write().await;
-                                        *pid_lock = Some(new_pid);
-                                    }
-
-                                    restart_count = 0;
-                                }
-                                Err(e) => {
-                                    error!("Failed to restart mihomo: {}", e);
-                                }
-                            }
-                        }
-                    } else {
-                        // 服务恢复运行，重置重启计数
-                        restart_count = 0;
-                    }
+        if let Some(since) = self.counters.gave_up_at {
+            match policy.cooldown() {
+                Some(cooldown) if since.elapsed() >= cooldown => {
+                    info!("Watchdog cooldown elapsed, retrying auto-restart");
+                    self.counters.attempts = 0;
+                    self.counters.gave_up_at = None;
                 }
+                _ => return,
             }
-        });
+        }
+
+        if self.counters.attempts >= policy.max_attempts {
+            error!(
+                "Max restart attempts ({}) reached, giving up",
+                policy.max_attempts
+            );
+            self.counters.gave_up_at = Some(Instant::now());
+            crate::events::emit_watchdog_gave_up(
+                &self.app_handle,
+                crate::events::WatchdogGaveUpEvent {
+                    attempts: self.counters.attempts,
+                    last_error: match reason {
+                        RestartReason::Crashed => controller.status().error,
+                        RestartReason::DataPath => controller.status().data_path.error,
+                    },
+                    retry_in_secs: policy.cooldown().map(|c| c.as_secs()),
+                    timestamp: crate::events::get_current_timestamp(),
+                },
+            );
+            return;
+        }
+
+        self.counters.attempts += 1;
+        let delay = policy.backoff_with_jitter(self.counters.attempts);
+        info!(
+            "Auto-restarting mihomo ({}) in {} ms (attempt {}/{})",
+            reason.as_str(),
+            delay.as_millis(),
+            self.counters.attempts,
+            policy.max_attempts
+        );
+
+        tokio::time::sleep(delay).await;
+
+        // 等待期间用户可能已手动启动或停止、暂停 watchdog 或关闭自动重启
+        if *self.paused.read().await
+            || !*self.auto_restart.read().await
+            || !reason.still_applies(&controller.status())
+        {
+            info!("Auto-restart cancelled while waiting");
+            return;
+        }
+
+        match controller.restart().await {
+            Ok(new_pid) => {
+                info!("Mihomo restarted successfully with PID: {}", new_pid);
+                self.counters.failures = 0;
+            }
+            Err(e) => {
+                error!("Failed to restart mihomo: {}", e);
+            }
+        }
+    }
+
+    /// 核心正常时重置重启计数
+    fn reset_when_stable(&mut self, policy: &WatchdogPolicy) {
+        // 稳定运行超过 cooldown 后才重置，避免启动后立即崩溃时无限重启
+        let stable_for =
+            crate::events::get_current_timestamp().saturating_sub(self.controller.status().since);
+        if self.counters.attempts > 0 && stable_for >= policy.cooldown_secs {
+            info!("Mihomo has been stable, resetting restart attempts");
+            self.counters.attempts = 0;
+        }
+        // 放弃后核心又被手动启动，重新开始计数
+        if self.counters.gave_up_at.take().is_some() {
+            self.counters.attempts = 0;
+        }
+    }
+}
+
+/// watchdog 重启核心的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RestartReason {
+    Crashed,
+    DataPath,
+}
+
+impl RestartReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RestartReason::Crashed => "crashed",
+            RestartReason::DataPath => "data path failing",
+        }
+    }
+
+    fn still_applies(&self, status: &crate::core_controller::CoreStatus) -> bool {
+        match self {
+            RestartReason::Crashed => status.state == CoreState::Crashed,
+            RestartReason::DataPath => {
+                status.state == CoreState::Running
+                    && status.data_path.state == DataPathState::Failing
+            }
+        }
+    }
+}
+
+/// 经由代理端口请求 probe_url，连续失败达到阈值后标记为 failing
+async fn check_data_path(controller: &CoreController, policy: &WatchdogPolicy) -> DataPathStatus {
+    let timeout = Duration::from_millis(policy.probe_timeout_ms);
+    let mut status = controller.status().data_path;
+
+    // 无法确定代理端口是配置问题（如只启用 TUN），不计为探测失败，也不会触发重启
+    let endpoint = match crate::data_path::proxy_endpoint().await {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            if status.state != DataPathState::Unknown {
+                warn!("Mihomo data path probe unavailable: {}", e);
+            }
+            status = DataPathStatus {
+                error: Some(e.to_string()),
+                ..DataPathStatus::new(DataPathState::Unknown)
+            };
+            controller.set_data_path(status.clone());
+            return status;
+        }
+    };
+    let result = crate::data_path::probe(&endpoint, &policy.probe_url, timeout).await;
+
+    match result {
+        Ok(latency) => {
+            status = DataPathStatus {
+                state: DataPathState::Healthy,
+                latency_ms: Some(latency),
+                error: None,
+                failures: 0,
+            };
+        }
+        Err(e) => {
+            status.failures += 1;
+            status.latency_ms = None;
+            status.error = Some(e.to_string());
+            if status.failures >= policy.data_path_failure_threshold {
+                if status.state != DataPathState::Failing {
+                    warn!(
+                        "Mihomo data path failing after {} probes: {}",
+                        status.failures, e
+                    );
+                }
+                status.state = DataPathState::Failing;
+            } else if status.state == DataPathState::Disabled {
+                status.state = DataPathState::Unknown;
+            }
+        }
+    }
+
+    controller.set_data_path(status.clone());
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = WatchdogPolicy {
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 5000,
+            backoff_multiplier: 2.0,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2000));
+        assert_eq!(policy.backoff(3), Duration::from_millis(4000));
+        assert_eq!(policy.backoff(4), Duration::from_millis(5000));
+        assert_eq!(policy.backoff(100), Duration::from_millis(5000));
+
+        for _ in 0..100 {
+            let delay = policy.backoff_with_jitter(2).as_millis();
+            assert!((1600..=2400).contains(&delay));
+        }
+
+        assert!(WatchdogPolicy {
+            jitter: 1.5,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
     }
 }
@@ -0,0 +1,861 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::ops::Range;
+use yaml_rust::YamlLoader;
+
+/// 超过该规模的列表不做逐项对比，直接整体替换
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// 把 `original` 修改为与 `config` 一致，只改写发生变化的键和列表项
+///
+/// 未改动部分的注释、键顺序、引号和缩进原样保留。修改后会重新解析校验，
+/// 结果与 `config` 不一致时返回错误，由调用方改为完整重写。
+pub fn update(original: &str, config: &Value) -> Result<String> {
+    let current = parse_first_document(original).context("Failed to parse original config")?;
+
+    let mut document = YamlDocument::parse(original);
+    document.apply(&mut Vec::new(), &current, config)?;
+    let updated = document.to_string();
+
+    let reparsed = parse_first_document(&updated).context("Edited YAML is invalid")?;
+    if reparsed != *config {
+        anyhow::bail!("Edited YAML does not match the expected config");
+    }
+    Ok(updated)
+}
+
+fn parse_first_document(text: &str) -> Result<Value> {
+    let documents = YamlLoader::load_from_str(text).context("Failed to parse YAML")?;
+    match documents.first() {
+        Some(yaml) => crate::config::yaml_to_json(yaml),
+        None => Ok(Value::Null),
+    }
+}
+
+/// 路径中的一段：映射的键或序列的下标
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// 值在文档中的形式
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// 块映射，`column` 为键所在的列
+    Mapping { lines: Range<usize>, column: usize },
+    /// 块序列，`column` 为 `-` 所在的列
+    Sequence { lines: Range<usize>, column: usize },
+    /// 标量、流式集合、块标量等，只能整体替换
+    Other,
+}
+
+/// 映射中的一个键或序列中的一项
+#[derive(Debug, Clone)]
+struct Slot {
+    line: usize,
+    /// 键或 `-` 所在的列
+    column: usize,
+    /// 同一行上值的字节范围（不含注释）
+    value: Range<usize>,
+    /// 值占用的后续行
+    block: Range<usize>,
+}
+
+impl Slot {
+    /// 包含该键或列表项的所有行
+    fn lines(&self) -> Range<usize> {
+        self.line..self.block.end.max(self.line + 1)
+    }
+}
+
+/// 保留注释和格式的 YAML 文档，按行定位块映射和块序列并做局部修改
+struct YamlDocument {
+    lines: Vec<String>,
+    newline: &'static str,
+    trailing_newline: bool,
+    indent_unit: usize,
+    /// 序列是否相对父键缩进（`key:\n  - a`）
+    indent_sequences: bool,
+}
+
+impl YamlDocument {
+    fn parse(text: &str) -> Self {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect();
+
+        let mut document = Self {
+            lines,
+            newline,
+            trailing_newline: text.is_empty() || text.ends_with('\n'),
+            indent_unit: 2,
+            indent_sequences: true,
+        };
+        document.detect_style();
+        document
+    }
+
+    /// 从已有的嵌套块推断缩进宽度和序列缩进风格
+    fn detect_style(&mut self) {
+        let mut found_unit = false;
+        let mut found_sequence = false;
+
+        for i in 0..self.lines.len() {
+            let Some(column) = self.indent(i) else {
+                continue;
+            };
+            let Some((_, value)) = parse_key(&self.lines[i], column) else {
+                continue;
+            };
+            if !self.lines[i][value.clone()].is_empty() {
+                continue;
+            }
+            let Some(next) = (i + 1..self.lines.len()).find(|&j| self.indent(j).is_some()) else {
+                continue;
+            };
+
+            let next_indent = self.indent(next).unwrap_or(0);
+            if is_item(&self.lines[next], next_indent) {
+                if !found_sequence {
+                    self.indent_sequences = next_indent > column;
+                    found_sequence = true;
+                }
+            } else if next_indent > column && !found_unit {
+                self.indent_unit = next_indent - column;
+                found_unit = true;
+            }
+
+            if found_unit && found_sequence {
+                break;
+            }
+        }
+    }
+
+    /// 内容行的缩进，空行和注释行返回 None
+    fn indent(&self, line: usize) -> Option<usize> {
+        let text = &self.lines[line];
+        let trimmed = text.trim_start_matches(' ');
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+        Some(text.len() - trimmed.len())
+    }
+
+    /// 第一个文档占用的行，跳过开头的指令和 `---`
+    fn root_lines(&self) -> Range<usize> {
+        let mut start = 0;
+        while start < self.lines.len() {
+            let line = self.lines[start].trim_end();
+            if self.indent(start).is_none() || line.starts_with('%') {
+                start += 1;
+            } else if line == "---" {
+                start += 1;
+                break;
+            } else {
+                break;
+            }
+        }
+
+        let end = (start..self.lines.len())
+            .find(|&i| {
+                let line = self.lines[i].trim_end();
+                line == "---" || line == "..." || line.starts_with("--- ")
+            })
+            .unwrap_or(self.lines.len());
+
+        start..end
+    }
+
+    fn root(&self) -> Node {
+        let lines = self.root_lines();
+        self.block_node(lines)
+    }
+
+    /// 根据第一行内容判断一段缩进块是映射还是序列
+    fn block_node(&self, lines: Range<usize>) -> Node {
+        let Some(first) = lines.clone().find(|&i| self.indent(i).is_some()) else {
+            return Node::Other;
+        };
+        let column = self.indent(first).unwrap_or(0);
+
+        if is_item(&self.lines[first], column) {
+            Node::Sequence { lines, column }
+        } else if parse_key(&self.lines[first], column).is_some() {
+            Node::Mapping { lines, column }
+        } else {
+            Node::Other
+        }
+    }
+
+    /// 某一行之后属于该值的行：缩进大于 `column` 的内容行，以及同列的序列项
+    fn extent(&self, line: usize, end: usize, column: usize, allow_items: bool) -> Range<usize> {
+        let mut last = line;
+        for i in line + 1..end {
+            let Some(indent) = self.indent(i) else {
+                continue;
+            };
+            let nested = indent > column
+                || (allow_items && indent == column && is_item(&self.lines[i], column));
+            if !nested {
+                break;
+            }
+            last = i;
+        }
+        line + 1..last + 1
+    }
+
+    fn entries(&self, lines: Range<usize>, column: usize) -> Vec<(String, Slot)> {
+        let mut entries = Vec::new();
+        for i in lines.clone() {
+            let Some((key, value)) = parse_key(&self.lines[i], column) else {
+                continue;
+            };
+            let allow_items = self.lines[i][value.clone()].is_empty();
+            let block = self.extent(i, lines.end, column, allow_items);
+            entries.push((
+                key,
+                Slot {
+                    line: i,
+                    column,
+                    value,
+                    block,
+                },
+            ));
+        }
+        entries
+    }
+
+    fn items(&self, lines: Range<usize>, column: usize) -> Vec<Slot> {
+        let mut items = Vec::new();
+        for i in lines.clone() {
+            let line = &self.lines[i];
+            if !is_item(line, column) {
+                continue;
+            }
+            let start = column + 1 + line[column + 1..].len()
+                - line[column + 1..].trim_start_matches(' ').len();
+            let value = start..start + value_len(&line[start..]);
+            let block = self.extent(i, lines.end, column, false);
+            items.push(Slot {
+                line: i,
+                column,
+                value,
+                block,
+            });
+        }
+        items
+    }
+
+    /// 键或列表项的值
+    fn slot_node(&self, slot: &Slot) -> Node {
+        let inline = &self.lines[slot.line][slot.value.clone()];
+        if inline.is_empty() {
+            return self.block_node(slot.block.clone());
+        }
+
+        // 列表项中紧跟 `- ` 的映射或嵌套序列
+        let start = slot.value.start;
+        if start > slot.column {
+            let lines = slot.lines();
+            if is_item(&self.lines[slot.line], start) {
+                return Node::Sequence {
+                    lines,
+                    column: start,
+                };
+            }
+            if parse_key(&self.lines[slot.line], start).is_some() {
+                return Node::Mapping {
+                    lines,
+                    column: start,
+                };
+            }
+        }
+        Node::Other
+    }
+
+    fn find_slot(&self, parent: &Node, segment: &Segment) -> Option<Slot> {
+        match (parent, segment) {
+            (Node::Mapping { lines, column }, Segment::Key(key)) => self
+                .entries(lines.clone(), *column)
+                .into_iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, slot)| slot),
+            (Node::Sequence { lines, column }, Segment::Index(index)) => {
+                self.items(lines.clone(), *column).into_iter().nth(*index)
+            }
+            _ => None,
+        }
+    }
+
+    fn node(&self, path: &[Segment]) -> Option<Node> {
+        let mut node = self.root();
+        for segment in path {
+            let slot = self.find_slot(&node, segment)?;
+            node = self.slot_node(&slot);
+        }
+        Some(node)
+    }
+
+    /// 把 `path` 处的值从 `old` 改为 `new`，尽量深入到最小的变化
+    fn apply(&mut self, path: &mut Vec<Segment>, old: &Value, new: &Value) -> Result<()> {
+        if old == new {
+            return Ok(());
+        }
+
+        match (old, new, self.node(path)) {
+            (Value::Object(old), Value::Object(new), Some(Node::Mapping { .. }))
+                if !new.is_empty() =>
+            {
+                // 先添加再删除，映射在修改过程中不会变空
+                for (key, value) in new {
+                    match old.get(key) {
+                        Some(previous) => {
+                            path.push(Segment::Key(key.clone()));
+                            self.apply(path, previous, value)?;
+                            path.pop();
+                        }
+                        None => self.insert_key(path, key, value)?,
+                    }
+                }
+                for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                    path.push(Segment::Key(key.clone()));
+                    self.remove(path)?;
+                    path.pop();
+                }
+                Ok(())
+            }
+            (Value::Array(old), Value::Array(new), Some(Node::Sequence { .. }))
+                if !new.is_empty() =>
+            {
+                self.apply_list(path, old, new)
+            }
+            _ => self.replace(path, new),
+        }
+    }
+
+    /// 对比新旧列表，只插入、删除或修改变化的项
+    fn apply_list(&mut self, path: &mut Vec<Segment>, old: &[Value], new: &[Value]) -> Result<()> {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_middle = &old[prefix..old.len() - suffix];
+        let new_middle = &new[prefix..new.len() - suffix];
+
+        if old_middle.len() * new_middle.len() > MAX_DIFF_CELLS {
+            let new = Value::Array(new.to_vec());
+            return self.replace(path, &new);
+        }
+
+        // 相同的项作为锚点，锚点之间的变化按位置配对修改，多余的删除或插入
+        let mut anchors = vec![(0, 0)];
+        anchors.extend(
+            longest_common_subsequence(old_middle, new_middle)
+                .into_iter()
+                .map(|(i, j)| (i + 1, j + 1)),
+        );
+        anchors.push((old_middle.len() + 1, new_middle.len() + 1));
+
+        // 从后往前修改，前面的下标保持不变
+        for window in anchors.windows(2).rev() {
+            let (old_range, new_range) =
+                (window[0].0..window[1].0 - 1, window[0].1..window[1].1 - 1);
+            let paired = old_range.len().min(new_range.len());
+
+            for k in (paired..old_range.len()).rev() {
+                path.push(Segment::Index(prefix + old_range.start + k));
+                self.remove(path)?;
+                path.pop();
+            }
+            for k in paired..new_range.len() {
+                let index = prefix + old_range.start + k;
+                self.insert_item(path, index, &new_middle[new_range.start + k])?;
+            }
+            for k in (0..paired).rev() {
+                path.push(Segment::Index(prefix + old_range.start + k));
+                let (before, after) = (
+                    &old_middle[old_range.start + k],
+                    &new_middle[new_range.start + k],
+                );
+                self.apply(path, before, after)?;
+                path.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// 整体替换 `path` 处的值，保留键所在行的注释
+    fn replace(&mut self, path: &[Segment], value: &Value) -> Result<()> {
+        let Some((last, parent_path)) = path.split_last() else {
+            let rendered = match render_inline(value) {
+                Some(inline) => vec![inline],
+                None => self.render_block(value, 0),
+            };
+            let root = self.root_lines();
+            self.lines.splice(root, rendered);
+            return Ok(());
+        };
+
+        let parent = self.node(parent_path).context("Config path not found")?;
+        let slot = self
+            .find_slot(&parent, last)
+            .context("Config key not found")?;
+        let line = self.lines[slot.line].clone();
+
+        let (first, block) = match (render_inline(value), last) {
+            (Some(inline), _) => {
+                let separator = if line[..slot.value.start].ends_with(' ') {
+                    ""
+                } else {
+                    " "
+                };
+                let first = format!(
+                    "{}{}{}{}",
+                    &line[..slot.value.start],
+                    separator,
+                    inline,
+                    &line[slot.value.end..]
+                );
+                (first, Vec::new())
+            }
+            (None, Segment::Key(_)) => {
+                let column = match (self.slot_node(&slot), value) {
+                    (Node::Mapping { column, .. }, Value::Object(_))
+                    | (Node::Sequence { column, .. }, Value::Array(_)) => column,
+                    _ => self.child_column(slot.column, value),
+                };
+                let first = format!(
+                    "{}{}",
+                    line[..slot.value.start].trim_end(),
+                    &line[slot.value.end..]
+                );
+                (first, self.render_block(value, column))
+            }
+            // 列表项中的映射或序列从 `- ` 后开始
+            (None, Segment::Index(_)) => {
+                let mut rendered = self.render_item(value, slot.column);
+                let first = rendered.remove(0);
+                (first, rendered)
+            }
+        };
+
+        let mut replacement = vec![first];
+        replacement.extend(block);
+        self.lines.splice(slot.lines(), replacement);
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &[Segment]) -> Result<()> {
+        let (last, parent_path) = path.split_last().context("Cannot remove the root")?;
+        let parent = self.node(parent_path).context("Config path not found")?;
+        if let Some(slot) = self.find_slot(&parent, last) {
+            self.lines.drain(slot.lines());
+        }
+        Ok(())
+    }
+
+    /// 在映射末尾添加新键
+    fn insert_key(&mut self, path: &[Segment], key: &str, value: &Value) -> Result<()> {
+        let Some(Node::Mapping { lines, column }) = self.node(path) else {
+            anyhow::bail!("Config path is not a block mapping");
+        };
+        let at = self
+            .entries(lines, column)
+            .last()
+            .map(|(_, slot)| slot.lines().end)
+            .context("Config mapping is empty")?;
+
+        let rendered = self.render_entry(key, value, column);
+        self.lines.splice(at..at, rendered);
+        Ok(())
+    }
+
+    /// 在序列的 `index` 处插入新项，紧跟在前一项之后
+    fn insert_item(&mut self, path: &[Segment], index: usize, value: &Value) -> Result<()> {
+        let Some(Node::Sequence { lines, column }) = self.node(path) else {
+            anyhow::bail!("Config path is not a block sequence");
+        };
+        let items = self.items(lines, column);
+        let at = match index {
+            0 => items.first().map(|item| item.line),
+            _ => items.get(index - 1).map(|item| item.lines().end),
+        }
+        .context("Config list index out of range")?;
+
+        let rendered = self.render_item(value, column);
+        self.lines.splice(at..at, rendered);
+        Ok(())
+    }
+
+    fn child_column(&self, column: usize, value: &Value) -> usize {
+        match value {
+            Value::Array(_) if !self.indent_sequences => column,
+            _ => column + self.indent_unit,
+        }
+    }
+
+    fn render_block(&self, value: &Value, column: usize) -> Vec<String> {
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .flat_map(|(key, value)| self.render_entry(key, value, column))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .flat_map(|item| self.render_item(item, column))
+                .collect(),
+            _ => vec![format!(
+                "{}{}",
+                " ".repeat(column),
+                render_inline(value).unwrap_or_default()
+            )],
+        }
+    }
+
+    fn render_entry(&self, key: &str, value: &Value, column: usize) -> Vec<String> {
+        let pad = " ".repeat(column);
+        let key = render_key(key);
+        match render_inline(value) {
+            Some(inline) => vec![format!("{}{}: {}", pad, key, inline)],
+            None => {
+                let mut lines = vec![format!("{}{}:", pad, key)];
+                lines.extend(self.render_block(value, self.child_column(column, value)));
+                lines
+            }
+        }
+    }
+
+    fn render_item(&self, value: &Value, column: usize) -> Vec<String> {
+        let pad = " ".repeat(column);
+        match render_inline(value) {
+            Some(inline) => vec![format!("{}- {}", pad, inline)],
+            None => {
+                let mut lines = self.render_block(value, column + 2);
+                lines[0] = format!("{}- {}", pad, &lines[0][column + 2..]);
+                lines
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for YamlDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.lines.join(self.newline))?;
+        if self.trailing_newline && !self.lines.is_empty() {
+            f.write_str(self.newline)?;
+        }
+        Ok(())
+    }
+}
+
+/// 标量和空集合写在同一行，非空集合返回 None
+fn render_inline(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(items) if items.is_empty() => Some("[]".to_string()),
+        Value::Object(map) if map.is_empty() => Some("{}".to_string()),
+        Value::Array(_) | Value::Object(_) => None,
+        _ => Some(render_scalar(value)),
+    }
+}
+
+fn render_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        // 多行或含控制字符的字符串用双引号，避免块标量的缩进问题
+        Value::String(s) if s.chars().any(char::is_control) => value.to_string(),
+        // ".inf" 等写回浮点数，与整体重写一致
+        Value::String(_) => {
+            render_yaml(&crate::config::json_to_yaml(value)).unwrap_or_else(|| value.to_string())
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// 整数、布尔形式的键不加引号写回
+fn render_key(key: &str) -> String {
+    if key.chars().any(char::is_control) {
+        return Value::String(key.to_string()).to_string();
+    }
+    render_yaml(&crate::config::json_key_to_yaml(key))
+        .unwrap_or_else(|| Value::String(key.to_string()).to_string())
+}
+
+fn render_yaml(value: &serde_yaml::Value) -> Option<String> {
+    serde_yaml::to_string(value)
+        .ok()
+        .map(|rendered| rendered.trim_end().to_string())
+}
+
+/// 行在 `column` 处是否为序列项（`-` 后跟空格或行尾）
+fn is_item(line: &str, column: usize) -> bool {
+    prefix_ok(line, column)
+        && line[column..].starts_with('-')
+        && matches!(line.as_bytes().get(column + 1), None | Some(b' '))
+}
+
+/// `column` 之前只能是缩进或外层列表项的 `- `
+fn prefix_ok(line: &str, column: usize) -> bool {
+    line.len() > column
+        && line.is_char_boundary(column)
+        && line[..column].bytes().all(|b| b == b' ' || b == b'-')
+        && (column == 0 || line.as_bytes()[column - 1] == b' ')
+        && line.as_bytes()[column] != b' '
+}
+
+/// 解析 `column` 处的 `key: value`，返回键和同一行上值的字节范围
+fn parse_key(line: &str, column: usize) -> Option<(String, Range<usize>)> {
+    if !prefix_ok(line, column) {
+        return None;
+    }
+    let text = &line[column..];
+
+    let (key, after_key) = match text.as_bytes()[0] {
+        b'"' => {
+            let end = closing_double_quote(text)?;
+            (serde_json::from_str(&text[..=end]).ok()?, end + 1)
+        }
+        b'\'' => {
+            let end = closing_single_quote(text)?;
+            (text[1..end].replace("''", "'"), end + 1)
+        }
+        b'-' | b'?' | b'#' | b'[' | b'{' | b'&' | b'*' | b'!' | b'|' | b'>' | b'%' | b'@' => {
+            if text.starts_with('-') && !matches!(text.as_bytes().get(1), None | Some(b' ')) {
+                plain_key(text)?
+            } else {
+                return None;
+            }
+        }
+        _ => plain_key(text)?,
+    };
+
+    let rest = &text[after_key..];
+    let rest = rest.trim_start_matches(' ');
+    let rest = rest.strip_prefix(':')?;
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+
+    let start = line.len() - rest.trim_start_matches(' ').len();
+    Some((key, start..start + value_len(&line[start..])))
+}
+
+/// 普通键：第一个后跟空格或行尾的 `:` 之前的部分
+fn plain_key(text: &str) -> Option<(String, usize)> {
+    let bytes = text.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'#' && i > 0 && bytes[i - 1] == b' ' {
+            return None;
+        }
+        if b == b':' && matches!(bytes.get(i + 1), None | Some(b' ')) {
+            let key = text[..i].trim_end();
+            return (!key.is_empty()).then(|| (key.to_string(), key.len()));
+        }
+    }
+    None
+}
+
+fn closing_double_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn closing_single_quote(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        if bytes[i] == b'\'' {
+            if bytes.get(i + 1) == Some(&b'\'') {
+                i += 2;
+                continue;
+            }
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 值的长度，不含行尾注释和空白
+fn value_len(text: &str) -> usize {
+    let (mut single, mut double, mut escaped) = (false, false, false);
+    let mut end = text.len();
+    // 引号只在标量开头（或流式集合的元素开头）才有引用作用，`it's` 中的不算
+    let opens_quote = |i: usize| i == 0 || text[..i].ends_with([' ', '[', '{', ',']);
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if double && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !single && !escaped && (double || opens_quote(i)) => double = !double,
+            '\'' if !double && (single || opens_quote(i)) => single = !single,
+            '#' if !single && !double && (i == 0 || text[..i].ends_with([' ', '\t'])) => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+
+    text[..end].trim_end().len()
+}
+
+/// 最长公共子序列的下标对
+fn longest_common_subsequence(a: &[Value], b: &[Value]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    let index = |i: usize, j: usize| i * (m + 1) + j;
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[index(i, j)] = if a[i] == b[j] {
+                table[index(i + 1, j + 1)] + 1
+            } else {
+                table[index(i + 1, j)].max(table[index(i, j + 1)])
+            };
+        }
+    }
+
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[index(i + 1, j)] >= table[index(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "\
+# 团队维护的配置
+mixed-port: 7890   # 本地代理端口
+mode: rule
+
+tun:
+  enable: false  # 默认关闭
+  stack: 'system'
+
+proxies:
+  - name: hk
+    type: ss
+    server: 'hk.example.com'
+  - name: jp
+    type: ss
+    server: jp.example.com
+
+rules:
+  # 局域网
+  - IP-CIDR,192.168.0.0/16,DIRECT
+  - GEOIP,CN,DIRECT
+  - MATCH,PROXY
+";
+
+    #[test]
+    fn test_targeted_edits_preserve_untouched_text() {
+        let mut config = parse_first_document(ORIGINAL).unwrap();
+        config["tun"]["enable"] = serde_json::json!(true);
+        config["tun"]["mtu"] = serde_json::json!(1500);
+        config["proxies"][1]["server"] = serde_json::json!("jp2.example.com");
+        config["rules"]
+            .as_array_mut()
+            .unwrap()
+            .insert(1, serde_json::json!("DOMAIN-SUFFIX,lan,DIRECT"));
+        config["dns"] = serde_json::json!({ "enable": true, "nameserver": ["223.5.5.5"] });
+
+        let expected = "\
+# 团队维护的配置
+mixed-port: 7890   # 本地代理端口
+mode: rule
+
+tun:
+  enable: true  # 默认关闭
+  stack: 'system'
+  mtu: 1500
+
+proxies:
+  - name: hk
+    type: ss
+    server: 'hk.example.com'
+  - name: jp
+    type: ss
+    server: jp2.example.com
+
+rules:
+  # 局域网
+  - IP-CIDR,192.168.0.0/16,DIRECT
+  - DOMAIN-SUFFIX,lan,DIRECT
+  - GEOIP,CN,DIRECT
+  - MATCH,PROXY
+dns:
+  enable: true
+  nameserver:
+    - 223.5.5.5
+";
+        assert_eq!(update(ORIGINAL, &config).unwrap(), expected);
+
+        // 删除键和列表项
+        let mut config = parse_first_document(ORIGINAL).unwrap();
+        config.as_object_mut().unwrap().remove("tun");
+        config["proxies"].as_array_mut().unwrap().remove(0);
+        let updated = update(ORIGINAL, &config).unwrap();
+        assert!(!updated.contains("tun:"));
+        assert!(!updated.contains("hk.example.com"));
+        assert!(updated.contains("  # 局域网\n"));
+    }
+
+    #[test]
+    fn test_anchors_and_merge_keys() {
+        let original = "\
+group-base: &base
+  type: url-test
+  interval: 300  # 测速间隔
+
+proxy-groups:
+  - <<: *base
+    name: auto
+  - <<: *base
+    name: backup
+    interval: 600
+";
+        // 修改显式写出的键，锚点和合并键保持原样
+        let mut config = parse_first_document(original).unwrap();
+        config["proxy-groups"][1]["name"] = serde_json::json!("fallback");
+        config["proxy-groups"][1]["interval"] = serde_json::json!(900);
+        let updated = update(original, &config).unwrap();
+        assert_eq!(
+            updated,
+            original
+                .replace("name: backup", "name: fallback")
+                .replace("interval: 600", "interval: 900")
+        );
+
+        // 通过合并键继承的键无法局部修改，由调用方整体重写
+        let mut config = parse_first_document(original).unwrap();
+        config["proxy-groups"][0]["interval"] = serde_json::json!(60);
+        assert!(update(original, &config).is_err());
+    }
+}